@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use lockvector::LockVector;
+use std::time::Duration;
+use workload::MIXES;
+
+fn mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lockvector/mixed");
+
+    for &mix in MIXES.iter() {
+        for &threads in [1, 2, 4, 8].iter() {
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", mix), threads), &threads, |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters).map(|_| workload::run_mixed::<LockVector<usize>>(threads, mix, 12800)).sum::<Duration>()
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn push_pop(c: &mut Criterion) {
+    c.bench_function("lockvector/push_back 1000", |b| {
+        b.iter(|| {
+            let v = LockVector::new(1);
+            for i in 0..1000usize {
+                v.push_back(i);
+            }
+            v
+        })
+    });
+
+    c.bench_function("lockvector/push_back+pop_back 1000", |b| {
+        b.iter(|| {
+            let v = LockVector::new(1000);
+            for i in 0..1000usize {
+                v.push_back(i);
+            }
+            for _ in 0..1000 {
+                v.pop_back();
+            }
+            v
+        })
+    });
+}
+
+criterion_group!(benches, mixed, push_pop);
+criterion_main!(benches);
@@ -1,35 +1,20 @@
+use std::mem;
 use std::sync::Mutex;
-use std::sync::atomic::AtomicUsize;
-use std::ops::{Add, AddAssign};
+use std::ops::AddAssign;
+
+// Elements are owned by the vector and dropped by the inner Vec, so any T
+// works. Reads hand out clones since the lock is gone once they return.
 #[derive(Debug)]
-pub struct LockVector<T: Copy + Eq + Add + AddAssign> {
+pub struct LockVector<T> {
     pub list: Mutex<Vec<T>>,
-    size: AtomicUsize,
 }
 
-impl <T> LockVector<T> 
-where 
-    T: Copy + Eq + Add + AddAssign
-{
+impl <T> LockVector<T> {
     pub fn new(size: usize) -> Self {
         LockVector {
             list: Mutex::new(Vec::with_capacity(size)),
-            size: AtomicUsize::new(size),
         }
     }
-    pub fn at(&self, index: usize) -> Option<T>{
-        let val = match self.list.lock() {
-            Ok(list) => {
-                if index < list.len(){
-                    return Some(list[index]);
-                }
-                None
-            },
-            Err(_) => {None},
-        };
-        val
-    }
-
     pub fn push_back(&self, value: T){
         let list = &mut self.list.lock().unwrap();
         list.push(value);
@@ -48,6 +33,17 @@ where
         None
     }
 
+    // Pushes `value` only if it becomes the element at `index`, that is if
+    // the vector holds exactly `index` elements, and hands it back if not.
+    pub fn try_push_at(&self, index: usize, value: T) -> Result<(), T> {
+        let list = &mut self.list.lock().unwrap();
+        if index != list.len() {
+            return Err(value);
+        }
+        list.push(value);
+        Ok(())
+    }
+
     pub fn insertat(&self, index: usize, value: T){
         let list = &mut self.list.lock().unwrap();
         if index <= list.len() {
@@ -55,22 +51,55 @@ where
         }
     }
 
+    pub fn length(&self) -> usize {
+        let list = &mut self.list.lock().unwrap();
+        list.len()
+    }
+}
+
+impl <T: Clone> LockVector<T> {
+    pub fn at(&self, index: usize) -> Option<T>{
+        let val = match self.list.lock() {
+            Ok(list) => {
+                if index < list.len(){
+                    return Some(list[index].clone());
+                }
+                None
+            },
+            Err(_) => {None},
+        };
+        val
+    }
+}
+
+impl <T: PartialEq> LockVector<T> {
     pub fn cwrite(&self, index: usize, old_value: T, new_value: T) -> bool{
         let list = &mut self.list.lock().unwrap();
-        if index < list.len() {
-            if old_value != new_value {
-                list[index] = new_value;
-                return true;
-            }
+        match list.get_mut(index) {
+            Some(value) if *value == old_value => {
+                *value = new_value;
+                true
+            },
+            _ => false,
         }
-        false
     }
+}
 
-    pub fn length(&self) -> usize {
+impl <T: PartialEq + Clone> LockVector<T> {
+    // Replaces the element at `index` with `new` if it equals `current`.
+    // Hands back the element it replaced, or else the one it found instead,
+    // None if `index` is past the end.
+    pub fn compare_exchange(&self, index: usize, current: T, new: T) -> Result<T, Option<T>> {
         let list = &mut self.list.lock().unwrap();
-        list.len()
+        match list.get_mut(index) {
+            Some(value) if *value == current => Ok(mem::replace(value, new)),
+            Some(value) => Err(Some(value.clone())),
+            None => Err(None),
+        }
     }
+}
 
+impl <T: AddAssign> LockVector<T> {
     pub fn addat(&self, index: usize, val: T) {
         let list = &mut self.list.lock().unwrap();
         if index < list.len(){
@@ -78,6 +107,30 @@ where
         }
 
     }
+}
 
+impl workload::ConcurrentVector for LockVector<usize> {
+    fn with_threads(capacity: usize, _num_threads: usize) -> Self {
+        LockVector::new(capacity)
+    }
 
+    fn push_back(&self, _tid: usize, value: usize) {
+        LockVector::push_back(self, value)
+    }
+
+    fn pop_back(&self, _tid: usize) -> Option<usize> {
+        LockVector::pop_back(self)
+    }
+
+    fn at(&self, _tid: usize, pos: usize) -> Option<usize> {
+        LockVector::at(self, pos)
+    }
+
+    fn cwrite(&self, _tid: usize, pos: usize, old: usize, new: usize) -> bool {
+        LockVector::cwrite(self, pos, old, new)
+    }
+
+    fn length(&self) -> usize {
+        LockVector::length(self)
+    }
 }
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+use lockvector::LockVector;
+
+#[test]
+fn seq_push_at() {
+    let vec = LockVector::new(2);
+    vec.push_back(10);
+    vec.push_back(20);
+
+    assert_eq!(vec.at(0), Some(10));
+    assert_eq!(vec.at(1), Some(20));
+    assert_eq!(vec.at(2), None);
+    assert_eq!(vec.length(), 2);
+}
+
+#[test]
+fn pop_back() {
+    let vec = LockVector::new(2);
+    vec.push_back(10);
+    vec.push_back(20);
+
+    assert_eq!(vec.pop_back(), Some(20));
+    assert_eq!(vec.pop_back(), Some(10));
+    assert_eq!(vec.pop_back(), None);
+
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn insertat_erase() {
+    let vec = LockVector::new(4);
+    vec.push_back(1);
+    vec.push_back(3);
+    vec.insertat(1, 2);
+
+    assert_eq!(vec.at(1), Some(2));
+    assert_eq!(vec.erase(0), Some(1));
+    assert_eq!(vec.erase(5), None);
+    assert_eq!(vec.length(), 2);
+}
+
+#[test]
+fn owned_values_are_dropped_once() {
+    let value = Arc::new(String::from("record"));
+    let vec = LockVector::new(2);
+
+    vec.push_back(value.clone());
+    vec.push_back(value.clone());
+    assert_eq!(Arc::strong_count(&value), 3);
+
+    assert_eq!(vec.at(0).as_deref(), Some(&value.to_string()));
+    drop(vec.pop_back());
+    assert_eq!(Arc::strong_count(&value), 2);
+
+    drop(vec);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
+
+#[test]
+fn compare_exchange_reports_what_it_found() {
+    let vec = LockVector::new(2);
+    vec.push_back(1);
+
+    assert_eq!(vec.compare_exchange(0, 1, 2), Ok(1));
+    assert_eq!(vec.compare_exchange(0, 1, 3), Err(Some(2)));
+    assert_eq!(vec.compare_exchange(1, 1, 3), Err(None));
+
+    assert!(!vec.cwrite(0, 1, 4));
+    assert!(vec.cwrite(0, 2, 4));
+    assert_eq!(vec.at(0), Some(4));
+}
+
+#[test]
+fn try_push_at_only_appends_at_the_end() {
+    let vec = LockVector::new(2);
+
+    assert_eq!(vec.try_push_at(1, 10), Err(10));
+    assert_eq!(vec.try_push_at(0, 10), Ok(()));
+    assert_eq!(vec.try_push_at(0, 20), Err(20));
+    assert_eq!(vec.try_push_at(1, 20), Ok(()));
+    assert_eq!(vec.length(), 2);
+    assert_eq!(vec.at(1), Some(20));
+}
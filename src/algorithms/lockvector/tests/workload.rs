@@ -0,0 +1,37 @@
+use lockvector::LockVector;
+use workload::{ConcurrentVector, MIXES};
+
+#[test]
+fn pushback_keeps_every_value() {
+    let num_threads = 4;
+    let per_thread = 30;
+    let v = workload::pushback::<LockVector<usize>>(num_threads, per_thread);
+
+    assert_eq!(ConcurrentVector::length(&*v), num_threads * per_thread);
+
+    let mut seen: Vec<usize> = (0..per_thread * num_threads).map(|i| v.at(i).unwrap()).collect();
+    seen.sort_unstable();
+
+    let mut expected: Vec<usize> = (0..num_threads)
+        .flat_map(|i| (0..per_thread).map(move |j| (i + 1) * 100 + j))
+        .collect();
+    expected.sort_unstable();
+
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn popback_drains_everything_once() {
+    let num_threads = 4;
+    let (v, popped) = workload::popback::<LockVector<usize>>(num_threads, 30);
+
+    assert_eq!(popped, (0..num_threads).sum());
+    assert_eq!(v.length(), 0);
+}
+
+#[test]
+fn mixed_runs_to_completion() {
+    for &mix in MIXES.iter() {
+        workload::run_mixed::<LockVector<usize>>(4, mix, 2000);
+    }
+}
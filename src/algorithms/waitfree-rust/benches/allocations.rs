@@ -0,0 +1,67 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use waitfree_rust::WaitFreeVector;
+
+// Not a criterion benchmark: counts the heap allocations each operation
+// makes once the vector is warmed up, which is what the descriptor pools
+// are there to bring down. Run with `cargo bench --bench allocations`.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+const OPS: usize = 100_000;
+
+fn per_op(op: impl Fn(usize)) -> f64 {
+    for i in 0..OPS {
+        op(i);
+    }
+
+    let before = ALLOCATIONS.load(SeqCst);
+    for i in 0..OPS {
+        op(i);
+    }
+    (ALLOCATIONS.load(SeqCst) - before) as f64 / OPS as f64
+}
+
+fn report(name: &str, make: impl Fn() -> WaitFreeVector) {
+    let vec = make();
+    let push_pop = per_op(|i| {
+        vec.push_back(0, i);
+        vec.pop_back(0);
+    });
+
+    let vec = make();
+    for i in 0..64 {
+        vec.push_back(0, i);
+    }
+    let at = per_op(|i| {
+        vec.at(0, i % 64);
+    });
+    let cwrite = per_op(|i| {
+        vec.cwrite(0, i % 64, i % 64, i % 64);
+    });
+
+    println!("{:<12} {:>14.3} {:>10.3} {:>10.3}", name, push_pop, at, cwrite);
+}
+
+fn main() {
+    println!("{:<12} {:>14} {:>10} {:>10}", "allocations", "push+pop", "at", "cwrite");
+    report("contiguous", || WaitFreeVector::new(64, 1));
+    report("segmented", || WaitFreeVector::new_segmented(64, 1));
+    report("bounded", || WaitFreeVector::bounded(128, 1));
+}
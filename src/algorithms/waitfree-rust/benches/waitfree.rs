@@ -0,0 +1,149 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use std::time::{Duration, Instant};
+use waitfree_rust::WaitFreeVector;
+use workload::MIXES;
+
+fn mixed(c: &mut Criterion) {
+    let mut group = c.benchmark_group("waitfree/mixed");
+
+    for &mix in MIXES.iter() {
+        for &threads in [1, 2, 4, 8].iter() {
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", mix), threads), &threads, |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters).map(|_| workload::run_mixed::<WaitFreeVector>(threads, mix, 12800)).sum::<Duration>()
+                })
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn push_pop(c: &mut Criterion) {
+    c.bench_function("waitfree/push_back 1000", |b| {
+        b.iter(|| {
+            let v = WaitFreeVector::new(1, 1);
+            for i in 0..1000 {
+                v.push_back(0, i);
+            }
+            v
+        })
+    });
+
+    c.bench_function("waitfree/segmented push_back 1000", |b| {
+        b.iter(|| {
+            let v = WaitFreeVector::new_segmented(1, 1);
+            for i in 0..1000 {
+                v.push_back(0, i);
+            }
+            v
+        })
+    });
+
+    c.bench_function("waitfree/push_back+pop_back 1000", |b| {
+        b.iter(|| {
+            let v = WaitFreeVector::new(1000, 1);
+            for i in 0..1000 {
+                v.push_back(0, i);
+            }
+            for _ in 0..1000 {
+                v.pop_back(0);
+            }
+            v
+        })
+    });
+}
+
+// How long the thread that triggers a resize is held up. Migration is left
+// to whoever touches the vector next, so this should not grow with the length.
+fn resize(c: &mut Criterion) {
+    let mut group = c.benchmark_group("waitfree/resize");
+
+    for &len in [1_000, 100_000].iter() {
+        group.bench_with_input(BenchmarkId::from_parameter(len), &len, |b, &len| {
+            b.iter_batched(
+                || {
+                    let v = WaitFreeVector::new(len, 1);
+                    for i in 0..len {
+                        v.push_back(0, i);
+                    }
+                    v
+                },
+                |v| {
+                    v.resize();
+                    v
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+// Threads pushing and popping at neighbouring positions, where sharing cache
+// lines between spots, or between the size and the per-thread tables, shows.
+// The group is named after the layout, so runs with and without the
+// padded-spots and padded-shared features end up next to each other.
+fn layout(c: &mut Criterion) {
+    let spots = if cfg!(feature = "padded-spots") { "padded" } else { "packed" };
+    let shared = if cfg!(feature = "padded-shared") { "padded" } else { "packed" };
+    let mut group = c.benchmark_group(format!("waitfree/layout/{} spots, {} shared", spots, shared));
+
+    for &threads in [2, 4, 8].iter() {
+        group.bench_with_input(BenchmarkId::new("push_back", threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                (0..iters).map(|_| {
+                    let start = Instant::now();
+                    workload::pushback::<WaitFreeVector>(threads, 1000);
+                    start.elapsed()
+                }).sum::<Duration>()
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("pop_back", threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                (0..iters).map(|_| {
+                    let start = Instant::now();
+                    workload::popback::<WaitFreeVector>(threads, 1000);
+                    start.elapsed()
+                }).sum::<Duration>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+// The mixed workloads and pushes again, with every atomic access SeqCst under the
+// seqcst feature and with the orderings derived for each otherwise. Named
+// like the layout group so the two runs end up next to each other.
+fn orderings(c: &mut Criterion) {
+    let orderings = if cfg!(feature = "seqcst") { "seqcst" } else { "derived" };
+    let mut group = c.benchmark_group(format!("waitfree/orderings/{}", orderings));
+
+    for &threads in [1, 4, 8].iter() {
+        for &mix in MIXES.iter() {
+            group.bench_with_input(BenchmarkId::new(format!("{:?}", mix), threads), &threads, |b, &threads| {
+                b.iter_custom(|iters| {
+                    (0..iters).map(|_| workload::run_mixed::<WaitFreeVector>(threads, mix, 12800)).sum::<Duration>()
+                })
+            });
+        }
+
+        group.bench_with_input(BenchmarkId::new("push_back", threads), &threads, |b, &threads| {
+            b.iter_custom(|iters| {
+                (0..iters).map(|_| {
+                    let start = Instant::now();
+                    workload::pushback::<WaitFreeVector>(threads, 1000);
+                    start.elapsed()
+                }).sum::<Duration>()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, mixed, push_pop, resize, layout, orderings);
+criterion_main!(benches);
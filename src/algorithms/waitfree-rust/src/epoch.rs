@@ -0,0 +1,373 @@
+// What an unsafe fn requires of its caller is in the comment above it, like
+// everywhere else in the crate, rather than in a rustdoc Safety section.
+#![allow(clippy::missing_safety_doc)]
+
+use std::cell::{Cell, UnsafeCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+
+use crate::sync::{fence, AtomicBool, AtomicPtr, AtomicUsize, Acquire, Relaxed, Release, SeqCst};
+
+// Epoch-based reclamation for everything the vector retires, with the same
+// interface as crossbeam-epoch's: pin() for a Guard, whose defer_unchecked
+// runs a closure once no thread pinned at the time can still be pinned.
+// It is our own so that every pointer it keeps is a pointer, with its
+// provenance, and the tests pass Miri with -Zmiri-strict-provenance.
+//
+// Every thread that pins gets a Local out of a global list. Locals are
+// never freed, a thread that exits gives its Local back and the next one to
+// start takes it over. While pinned, a Local holds the global epoch it saw.
+// The epoch moves on, in steps of STEP, only once every pinned Local holds
+// the current one, so while anybody is pinned at `e` it stays at most one
+// step ahead.
+//
+// A closure deferred by a thread pinned at `e` runs once the epoch is three
+// steps past `e`. Whoever could still reach what the closure frees pinned
+// before it was retired, so at most one step past `e`, and the epoch cannot
+// take its third step until they have unpinned. The closures wait in their
+// thread's bag, and a thread that exits hangs its bag off its Local for the
+// others to run, so a flush anywhere gets to everything eventually.
+struct Global {
+    epoch: AtomicUsize,
+    locals: AtomicPtr<Local>,
+}
+
+#[cfg(not(loom))]
+static GLOBAL: Global = Global {
+    epoch: AtomicUsize::new(0),
+    locals: AtomicPtr::new(ptr::null_mut()),
+};
+
+// Loom's atomics cannot be made in a const, and its statics only last for
+// one execution. Thread exit does not touch GLOBAL for that reason: loom
+// drops the statics before the main thread's locals.
+#[cfg(loom)]
+loom::lazy_static! {
+    static ref GLOBAL: Global = Global {
+        epoch: AtomicUsize::new(0),
+        locals: AtomicPtr::new(ptr::null_mut()),
+    };
+}
+
+// The low bit of a Local's state says it is pinned, the rest is the epoch.
+const PINNED: usize = 1;
+const STEP: usize = 2;
+
+// How many pins go by between two collects of a thread that never flushes.
+const PINS_BETWEEN_COLLECT: usize = 128;
+
+struct Local {
+    // Set before the Local is published and never changed after.
+    next: *mut Local,
+    in_use: AtomicBool,
+    state: AtomicUsize,
+
+    // Only the thread that has the Local in use touches the rest.
+    guards: Cell<usize>,
+    // Whether a thread-local Handle holds the Local, see release.
+    handle: Cell<bool>,
+    pins: Cell<usize>,
+    // Deferred closures with the epoch their thread was pinned at, oldest
+    // first.
+    bag: UnsafeCell<VecDeque<(usize, Deferred)>>,
+    // Bags of threads that had this Local before, see release.
+    orphans: AtomicPtr<Orphan>,
+}
+
+unsafe impl Sync for Local {}
+
+struct Orphan {
+    bag: VecDeque<(usize, Deferred)>,
+    next: *mut Orphan,
+}
+
+impl Local {
+    // Takes over a Local nobody uses, or adds a new one to the list.
+    fn acquire() -> &'static Local {
+        let mut current = GLOBAL.locals.load(Acquire);
+        while let Some(local) = unsafe { current.as_ref() } {
+            if local.in_use.compare_exchange(false, true, Acquire, Relaxed).is_ok() {
+                local.handle.set(true);
+                return local;
+            }
+            current = local.next;
+        }
+
+        let local = Box::into_raw(Box::new(Local {
+            next: ptr::null_mut(),
+            in_use: AtomicBool::new(true),
+            state: AtomicUsize::new(0),
+            guards: Cell::new(0),
+            handle: Cell::new(true),
+            pins: Cell::new(0),
+            bag: UnsafeCell::new(VecDeque::new()),
+            orphans: AtomicPtr::new(ptr::null_mut()),
+        }));
+
+        let mut head = GLOBAL.locals.load(Relaxed);
+        loop {
+            // Nobody else sees the Local before the CAS succeeds.
+            unsafe { (*local).next = head };
+            match GLOBAL.locals.compare_exchange(head, local, Release, Relaxed) {
+                Ok(_) => return unsafe { &*local },
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    // Gives the Local back once neither a Handle nor a Guard holds it. What
+    // is still in its bag goes to `orphans`, where any collect finds it.
+    fn release(&self) {
+        let bag = mem::take(unsafe { &mut *self.bag.get() });
+        if !bag.is_empty() {
+            self.adopt(Box::into_raw(Box::new(Orphan { bag, next: ptr::null_mut() })));
+        }
+
+        self.in_use.store(false, Release);
+    }
+
+    fn adopt(&self, orphan: *mut Orphan) {
+        let mut head = self.orphans.load(Relaxed);
+        loop {
+            // The orphan is ours alone until the CAS succeeds.
+            unsafe { (*orphan).next = head };
+            match self.orphans.compare_exchange(head, orphan, Release, Relaxed) {
+                Ok(_) => return,
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    fn pin(&self) {
+        let guards = self.guards.get();
+        self.guards.set(guards + 1);
+        if guards > 0 {
+            return;
+        }
+
+        // Whoever moves the epoch on next either sees us pinned or fences
+        // before we do, and then we see everything retired before that.
+        let epoch = GLOBAL.epoch.load(Relaxed);
+        self.state.store(epoch | PINNED, Relaxed);
+        fence(SeqCst);
+
+        let pins = self.pins.get().wrapping_add(1);
+        self.pins.set(pins);
+        if pins.is_multiple_of(PINS_BETWEEN_COLLECT) {
+            self.collect();
+        }
+    }
+
+    fn unpin(&self) {
+        let guards = self.guards.get() - 1;
+        self.guards.set(guards);
+        if guards > 0 {
+            return;
+        }
+
+        self.state.store(0, Release);
+        if !self.handle.get() {
+            self.release();
+        }
+    }
+
+    // Only while pinned, the epoch it is tagged with is the one we hold.
+    fn defer(&self, deferred: Deferred) {
+        let epoch = self.state.load(Relaxed) & !PINNED;
+        unsafe { &mut *self.bag.get() }.push_back((epoch, deferred));
+    }
+
+    // Moves the epoch on if everybody pinned has caught up, then runs what
+    // has waited long enough in our bag and in the orphaned ones.
+    fn collect(&self) {
+        let epoch = try_advance();
+
+        // The bag is not borrowed while a closure runs, which may defer more.
+        loop {
+            let next = match unsafe { &mut *self.bag.get() } {
+                bag if bag.front().is_some_and(|&(tag, _)| expired(epoch, tag)) => bag.pop_front(),
+                _ => None,
+            };
+            match next {
+                Some((_, deferred)) => deferred.call(),
+                None => break,
+            }
+        }
+
+        let mut current = GLOBAL.locals.load(Acquire);
+        while let Some(local) = unsafe { current.as_ref() } {
+            local.collect_orphans(epoch);
+            current = local.next;
+        }
+    }
+
+    // Takes all orphans at once, so nobody else runs them meanwhile, and
+    // puts back those that still hold something.
+    fn collect_orphans(&self, epoch: usize) {
+        let mut current = self.orphans.swap(ptr::null_mut(), Acquire);
+        while !current.is_null() {
+            let mut orphan = unsafe { Box::from_raw(current) };
+            current = orphan.next;
+
+            while orphan.bag.front().is_some_and(|&(tag, _)| expired(epoch, tag)) {
+                let (_, deferred) = orphan.bag.pop_front().unwrap();
+                deferred.call();
+            }
+
+            if !orphan.bag.is_empty() {
+                self.adopt(Box::into_raw(orphan));
+            }
+        }
+    }
+}
+
+// Returns the epoch, moved on a step if every pinned Local already holds it.
+fn try_advance() -> usize {
+    let epoch = GLOBAL.epoch.load(Relaxed);
+    fence(SeqCst);
+
+    let mut current = GLOBAL.locals.load(Acquire);
+    while let Some(local) = unsafe { current.as_ref() } {
+        let state = local.state.load(Relaxed);
+        if state & PINNED != 0 && state & !PINNED != epoch {
+            return epoch;
+        }
+        current = local.next;
+    }
+
+    // Whatever the Locals did before they unpinned happens before the
+    // closures this lets run.
+    fence(Acquire);
+    match GLOBAL.epoch.compare_exchange(epoch, epoch.wrapping_add(STEP), Release, Relaxed) {
+        Ok(_) => epoch.wrapping_add(STEP),
+        Err(current) => current,
+    }
+}
+
+fn expired(epoch: usize, tag: usize) -> bool {
+    epoch.wrapping_sub(tag) >= 3 * STEP
+}
+
+// Holds a thread's Local and gives it back when the thread exits.
+struct Handle {
+    local: &'static Local,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.local.handle.set(false);
+        if self.local.guards.get() == 0 {
+            self.local.release();
+        }
+    }
+}
+
+#[cfg(not(loom))]
+thread_local! {
+    static HANDLE: Handle = Handle { local: Local::acquire() };
+}
+
+#[cfg(loom)]
+loom::thread_local! {
+    static HANDLE: Handle = Handle { local: Local::acquire() };
+}
+
+// A closure of up to three words kept inline, a bigger one boxed, so most
+// retirements do not allocate.
+type Data = [MaybeUninit<usize>; 3];
+
+struct Deferred {
+    call: unsafe fn(*mut Data),
+    data: Data,
+}
+
+impl Deferred {
+    fn new<F: FnOnce()>(f: F) -> Deferred {
+        let mut data: Data = [MaybeUninit::uninit(); 3];
+
+        if mem::size_of::<F>() <= mem::size_of::<Data>() && mem::align_of::<F>() <= mem::align_of::<Data>() {
+            unsafe fn call<F: FnOnce()>(data: *mut Data) {
+                ptr::read(data.cast::<F>())()
+            }
+
+            unsafe { ptr::write(data.as_mut_ptr().cast::<F>(), f) };
+            Deferred { call: call::<F>, data }
+        } else {
+            unsafe fn call<F: FnOnce()>(data: *mut Data) {
+                ptr::read(data.cast::<Box<F>>())()
+            }
+
+            unsafe { ptr::write(data.as_mut_ptr().cast::<Box<F>>(), Box::new(f)) };
+            Deferred { call: call::<F>, data }
+        }
+    }
+
+    fn call(mut self) {
+        unsafe { (self.call)(&mut self.data) }
+    }
+}
+
+// Keeps what the thread loads while pinned from being freed. Guards nest,
+// the thread stays pinned until the last one is dropped.
+pub struct Guard {
+    local: Option<&'static Local>,
+    // Pinning is per thread.
+    thread: PhantomData<*mut ()>,
+}
+
+pub fn pin() -> Guard {
+    // A thread whose thread-locals are gone already pins a Local of its own,
+    // which goes back when the guard does.
+    let local = HANDLE.try_with(|handle| handle.local).unwrap_or_else(|_| {
+        let local = Local::acquire();
+        local.handle.set(false);
+        local
+    });
+
+    local.pin();
+    Guard { local: Some(local), thread: PhantomData }
+}
+
+struct Unprotected(Guard);
+
+unsafe impl Sync for Unprotected {}
+
+static UNPROTECTED: Unprotected = Unprotected(Guard { local: None, thread: PhantomData });
+
+// A guard that protects nothing: what is deferred on it runs right away.
+// Only for code that no other thread can run alongside, like a Drop.
+pub unsafe fn unprotected() -> &'static Guard {
+    &UNPROTECTED.0
+}
+
+impl Guard {
+    // Runs `f` once no thread pinned now can still be pinned. `f` may run on
+    // any thread, and whatever it touches has to live that long.
+    pub unsafe fn defer_unchecked<F: FnOnce() -> R, R>(&self, f: F) {
+        match self.local {
+            Some(local) => local.defer(Deferred::new(move || drop(f()))),
+            None => drop(f()),
+        }
+    }
+
+    pub fn defer<F: FnOnce() -> R + Send + 'static, R>(&self, f: F) {
+        unsafe { self.defer_unchecked(f) }
+    }
+
+    // Moves the epoch on if it can and runs whatever has waited long enough.
+    pub fn flush(&self) {
+        if let Some(local) = self.local {
+            local.collect();
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if let Some(local) = self.local {
+            local.unpin();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::fmt;
+
+// How a contiguous vector picks the capacity of the generation that
+// replaces a full one. Segmented storage allocates buckets of fixed sizes
+// and only looks at the maximum of Bounded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum GrowthPolicy {
+    // capacity * 2 + 1, the +1 so that an empty storage grows as well.
+    #[default]
+    Double,
+    // capacity * 3 / 2 + 1, for when memory is tighter than copies are slow.
+    Half,
+    // Straight to the given capacity the first time the vector outgrows its
+    // own, doubling after that. Meant for filling the vector in bulk.
+    JumpTo(usize),
+    // Doubles, but never holds more than the given number of elements. A
+    // push past that fails with CapacityExceeded instead of growing.
+    Bounded(usize),
+    // All the spots for the given number of elements are allocated up front
+    // and the storage is never replaced, neither to grow nor to shrink. A
+    // push past the end fails like with Bounded. See WaitFreeVector::bounded.
+    Fixed(usize),
+}
+
+impl GrowthPolicy {
+    // The capacity to grow a generation of `capacity` spots to when spot
+    // `needed - 1` has to exist.
+    pub(crate) fn next_capacity(&self, capacity: usize, needed: usize) -> usize {
+        let doubled = capacity * 2 + 1;
+        match *self {
+            GrowthPolicy::Double => doubled.max(needed),
+            GrowthPolicy::Half => (capacity + capacity / 2 + 1).max(needed),
+            GrowthPolicy::JumpTo(target) if capacity < target => target.max(needed),
+            GrowthPolicy::JumpTo(_) => doubled.max(needed),
+            GrowthPolicy::Bounded(_) => self.clamp(doubled.max(needed)),
+            GrowthPolicy::Fixed(_) => self.clamp(capacity),
+        }
+    }
+
+    // Capacity of the first generation when `capacity` was asked for.
+    pub(crate) fn initial_capacity(&self, capacity: usize) -> usize {
+        match *self {
+            GrowthPolicy::Fixed(_) => self.clamp(usize::MAX),
+            _ => self.clamp(capacity),
+        }
+    }
+
+    // Whether the storage stays the one the vector was made with.
+    pub(crate) fn is_fixed(&self) -> bool {
+        matches!(self, GrowthPolicy::Fixed(_))
+    }
+
+    // Most elements the vector may hold.
+    pub(crate) fn limit(&self) -> usize {
+        match *self {
+            GrowthPolicy::Bounded(max) | GrowthPolicy::Fixed(max) => max,
+            _ => usize::MAX,
+        }
+    }
+
+    // Storage never needs more than one spot past the limit: a pop places its
+    // descriptor right above the last element, even on a full vector.
+    pub(crate) fn clamp(&self, capacity: usize) -> usize {
+        capacity.min(self.limit().saturating_add(1))
+    }
+}
+
+// A push that would have taken the vector past the maximum of its
+// GrowthPolicy::Bounded or Fixed. Hands the value back.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CapacityExceeded<T>(pub T);
+
+impl<T> fmt::Display for CapacityExceeded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "vector is at its maximum capacity")
+    }
+}
+
+impl<T: fmt::Debug> Error for CapacityExceeded<T> {}
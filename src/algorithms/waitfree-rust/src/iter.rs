@@ -0,0 +1,62 @@
+use crate::epoch::Guard;
+use crate::word::SlotWord;
+use crate::{loadstate, value_base, BaseDescr, WaitFreeVector, STATE_PASSED};
+
+// Weakly consistent iterator over a WaitFreeVector, in the spirit of the
+// iterators of java.util.concurrent. It reads every spot exactly once, in
+// order, while other threads keep pushing, popping and resizing:
+//
+// - it never panics and never hands out freed memory, because everything it
+//   reads stays protected by `guard`, which is also why it can hand out
+//   references where at() has to clone, and a pop of a value handed out
+//   clones it rather than moving it away;
+// - a resize behind its back is followed through load_at, so it always
+//   reads the newest generation of each spot;
+// - empty spots are skipped, and a spot holding a descriptor yields what
+//   value_base reports for it, so a value that is about to be popped may
+//   still show up. A push only shows up once it has PASSED, like for at(),
+//   but one still undecided is skipped rather than helped along.
+//
+// It is not a snapshot: values seen together may never have been in the
+// vector at the same time. Use WaitFreeVector::snapshot for that.
+pub struct Iter<'g, T> {
+    vector: &'g WaitFreeVector<T>,
+    guard: &'g Guard,
+    position: usize,
+}
+
+impl<'g, T> Iter<'g, T> {
+    pub(crate) fn new(vector: &'g WaitFreeVector<T>, guard: &'g Guard) -> Iter<'g, T> {
+        Iter {
+            vector,
+            guard,
+            position: 0,
+        }
+    }
+}
+
+impl<'g, T: Clone + Send + Sync + 'static> Iterator for Iter<'g, T> {
+    type Item = &'g T;
+
+    fn next(&mut self) -> Option<&'g T> {
+        // The capacity is read again every time since it changes under us.
+        while self.position < self.vector.capacity() {
+            let (_, word) = self.vector.load_at(self.position, self.guard);
+            self.position += 1;
+
+            let value = match unsafe { SlotWord::decode(word) } {
+                SlotWord::Value(value) => Some(value),
+                SlotWord::Descr(BaseDescr::PushDescrType(descr)) if loadstate(descr) != STATE_PASSED => None,
+                SlotWord::Descr(descr) => value_base(descr),
+                _ => None,
+            };
+
+            // A value a pop has taken already is skipped like an empty spot.
+            if let Some(value) = value.and_then(|value| unsafe { value.hold::<T>() }) {
+                return Some(value);
+            }
+        }
+
+        None
+    }
+}
@@ -1,28 +1,168 @@
-use std::rc::Rc;
-use std::sync::Arc;
-use crossbeam_epoch::{self as epoch, Atomic, Guard, Shared, Owned};
-use std::sync::atomic::Ordering::{SeqCst, Release, Acquire};
-use std::sync::atomic::{AtomicUsize, AtomicBool};
+// The tag constants keep the names used in the paper and the C++ version.
+#![allow(non_upper_case_globals)]
+
+use std::marker::PhantomData;
+use std::ptr;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, PoisonError, Weak};
+use crossbeam_utils::CachePadded;
+use epoch::Guard;
+use sync::{fence, AtomicBool, AtomicU64, AtomicUsize, AtomicU8, AcqRel, Acquire, Relaxed, Release, SeqCst};
+
+mod sync;
+
+pub mod epoch;
+
+mod segmented;
+use segmented::Segmented;
+
+mod iter;
+pub use iter::Iter;
+
+mod growth;
+pub use growth::{CapacityExceeded, GrowthPolicy};
+
+pub mod markable;
+pub use markable::{AtomicMarkablePtr, MarkedPtr};
+
+mod pool;
+use pool::{DescrPool, ValuePool};
+
+mod reclaim;
+use reclaim::{Pinned, Reclaim};
+
+mod word;
+use word::{pack_shared, release_descr, SlotWord, ValuePtr};
+pub use word::{pack_descr, unpack_descr};
 
 const TagNotValue: usize = 1;
 const TagNotCopied: usize = 2;
 const TagDescr: usize = 3;
+// Not a tag of its own but a bit on top of the others: a resize sets it on
+// every spot of the old generation it copies, so the spot can no longer change.
 const TagResize: usize = 4;
 
-const NO_RESULT: usize = usize::MAX;
-
 const LIMIT: usize = 1000;
 
-type Spot = Arc<Atomic<usize>>;
+// Push and pop descriptors each thread keeps for reuse, allocated up front
+// for a fixed vector and as they are needed otherwise. A descriptor comes
+// back once the epoch it was retired in is over, so this has to cover what
+// a thread places in the meantime.
+#[cfg(not(loom))]
+const POOL_SIZE: usize = 256;
+// Small enough for loom to get to the reuse within a few operations.
+#[cfg(loom)]
+const POOL_SIZE: usize = 2;
+
+// Number of spots a thread claims at once when helping a resize migrate.
+const MIGRATION_CHUNK: usize = 256;
+
+// Bits of the size word that hold the element count, the rest is a version.
+const SIZE_BITS: u32 = usize::BITS * 5 / 8;
+const SIZE_MASK: usize = (1 << SIZE_BITS) - 1;
+
+// Memory orderings. Whatever one thread hands to another goes through a CAS
+// on a spot, a descriptor's state or child, an op's result or a generation
+// pointer, and whoever finds it there goes on to read what it points to: a
+// boxed value, the fields of a descriptor or op, the spots of a generation.
+// So CASes are AcqRel and loads Acquire. Helpers that race to decide the
+// same thing all CAS the same word, and the modification order of that one
+// word already puts them in sequence, so none of this needs SeqCst.
+//
+// Two things do. Seals of a shrink_to_fit have to agree with its state, see
+// help_shrink. And readers of two different spots must not disagree on the
+// order of two writes to them: at() and the like get that from the SeqCst
+// fence epoch::pin issues, snapshots fence between their collects.
+type Spot = Arc<SpotWord>;
+
+// Spots sit next to each other, so threads at neighbouring positions keep
+// taking the same cache line from each other. The padded-spots feature
+// gives every spot a line of its own, at the cost of a line per spot.
+#[cfg(not(feature = "padded-spots"))]
+type SpotWord = AtomicMarkablePtr<usize>;
+#[cfg(feature = "padded-spots")]
+type SpotWord = CachePadded<AtomicMarkablePtr<usize>>;
+
+// The conversion only does something with padded-spots.
+#[allow(clippy::useless_conversion)]
+fn make_spot(u: MarkedPtr<usize>) -> Spot {
+    Arc::new(AtomicMarkablePtr::from(u).into())
+}
+
+// A frozen spot belongs to a generation that has already been copied into
+// its successor. Its word is still readable but every CAS on it fails.
+fn is_frozen(word: MarkedPtr<usize>) -> bool {
+    word.mark() & TagResize != 0
+}
+
+// Whether `found`, out of a spot, equals the value `expected` an op owns.
+// A pop that takes `found` while we look leaves nothing there to match.
+fn same_value<T: Clone + PartialEq>(expected: ValuePtr, found: ValuePtr) -> bool {
+    unsafe { found.peek(|found: &T| expected.get::<T>() == found) }.unwrap_or(false)
+}
+
+// Every helper reads the entries of other threads, and every push and pop
+// the size, so threads announcing ops or moving the size disturb each other
+// on shared lines. The padded-shared feature gives the size and each entry
+// of the announcement and helping tables a cache line of its own.
+#[cfg(not(feature = "padded-shared"))]
+type Padded<T> = T;
+#[cfg(feature = "padded-shared")]
+type Padded<T> = CachePadded<T>;
+
+// The conversion only does something with padded-shared.
+#[allow(clippy::useless_conversion)]
+fn padded<T>(value: T) -> Padded<T> {
+    value.into()
+}
+
+type OpSpot = Padded<AtomicMarkablePtr<BaseOp>>;
+
+fn make_op_spot(u: MarkedPtr<BaseOp>) -> OpSpot {
+    padded(AtomicMarkablePtr::from(u))
+}
 
-fn make_spot(u: Shared<usize>) -> Spot {
-    return Arc::new(Atomic::from(u));
+// Number of elements in the vector. It is not bumped by the operation that
+// owns a descriptor but by whoever completes the descriptor, right before the
+// descriptor leaves its spot. So an element is counted before anybody can
+// read it, and length() agrees with what at() sees.
+//
+// Several helpers may complete the same descriptor. Each one moves the count
+// from `from` to `to` only while the descriptor is still pending, and the
+// version in the upper bits changes with every update, so a helper that
+// stalled after that check cannot apply a push or pop a second time.
+struct Size {
+    word: AtomicUsize,
 }
 
-type OpSpot = Arc<Atomic<BaseOp>>;
+impl Size {
+    fn new() -> Size {
+        Size { word: AtomicUsize::new(0) }
+    }
+
+    fn load(&self) -> usize {
+        self.word.load(Acquire) & SIZE_MASK
+    }
+
+    // The count together with its version. Two equal loads mean the count
+    // did not change at all in between.
+    fn load_versioned(&self) -> usize {
+        self.word.load(Acquire)
+    }
+
+    fn update(&self, from: usize, to: usize, pending: impl Fn() -> bool) {
+        loop {
+            let word = self.word.load(Acquire);
+            if word & SIZE_MASK != from || !pending() {
+                return;
+            }
 
-fn make_op_spot(u: Shared<BaseOp>) -> OpSpot {
-    return Arc::new(Atomic::from(u));
+            let version = (word >> SIZE_BITS).wrapping_add(1);
+            if self.word.compare_exchange(word, (version << SIZE_BITS) | to, AcqRel, Acquire).is_ok() {
+                return;
+            }
+        }
+    }
 }
 
 // replace pushstate enum
@@ -32,170 +172,475 @@ const STATE_PASSED: u8 = 0x02;
 
 #[derive(Clone)]
 pub enum BaseDescr {
-    PushDescrType(PushDescr),
-    PopDescrType(Rc<PopDescr>),
-    PopSubDescrType(Rc<PopSubDescr>),
+    PushDescrType(Arc<PushDescr>),
+    PopDescrType(Arc<PopDescr>),
+    PopSubDescrType(Arc<PopSubDescr>),
+    WriteDescrType(Arc<WriteDescr>),
+    MultiDescrType(Arc<MultiSlotDescr>),
 }
 
 // contains the value to be pushed and a state member
-#[derive(Clone)]
 pub struct PushDescr {
     // vec: Atomic<WaitFreeVector>,
-    owner: Atomic<BaseOp>,
-    value: usize,
+    owner: Option<Arc<PushOp>>,
+    value: ValuePtr,
     pos: usize,
-    state: Atomic<u8>,
+    state: AtomicU8,
+    // Some for a descriptor the pool of a fixed vector keeps, see DescrPool.
+    retired: Option<AtomicUsize>,
 }
 
 impl PushDescr {
-    pub fn new(pos: usize, value: usize) -> PushDescr {
+    pub(crate) fn new(pos: usize, value: ValuePtr) -> PushDescr {
         PushDescr {
-            owner: Atomic::null(),
+            owner: None,
             // vec,
             pos,
             value,
-            state: Atomic::new(STATE_UNDECIDED),
+            state: AtomicU8::new(STATE_UNDECIDED),
+            retired: None,
         }
     }
-}
 
-pub fn pack_descr(descr: BaseDescr, guard: &Guard) -> Shared<usize> {
-    let ptr = Owned::new(descr).with_tag(TagDescr).into_shared(guard);
-    let masked: Shared<usize> = unsafe { std::mem::transmute(ptr) };
-    masked
+    // A descriptor placed by whoever helps the announced push `owner`.
+    pub fn with_owner(pos: usize, owner: Arc<PushOp>) -> PushDescr {
+        PushDescr {
+            value: owner.value,
+            owner: Some(owner),
+            pos,
+            state: AtomicU8::new(STATE_UNDECIDED),
+            retired: None,
+        }
+    }
 }
 
-pub fn unpack_descr<'g>(curr: Shared<usize>, guard: &'g Guard) -> Option<Shared<'g, BaseDescr>> {
-    let unmasked: Shared<BaseDescr> = unsafe { std::mem::transmute(curr) };
-
-    if unmasked.tag() == TagDescr {
-        Some(unmasked)
+// Frees the descriptor behind `word` once nobody pinned can still be
+// looking at it. Only whoever took `word` out of its spot may call this.
+unsafe fn retire_descr(word: MarkedPtr<usize>, guard: &Guard) {
+    if let Some(descr) = unpack_descr(word, guard) {
+        guard.defer_unchecked(move || drop(release_descr(descr)));
     }
-    else {
-        None
+}
+
+// Frees a descriptor that never made it into a spot.
+unsafe fn drop_descr(word: MarkedPtr<usize>, guard: &Guard) {
+    if let Some(descr) = unpack_descr(word, guard) {
+        drop(release_descr(descr));
     }
 }
 
-pub fn loadstate<'g>(newdescr: &PushDescr, guard: &'g Guard) -> (Shared<'g, u8>, u8) {
-    let mystate = newdescr.state.load(SeqCst, guard);
-    if mystate.is_null() {
-        panic!("STATE OF A DESCRIPTOR WAS NULL")
+// A pop descriptor whose child is decided no longer guards anything: the pop
+// either took the value below it or failed, and the spot itself is empty.
+fn is_spent_pop(word: MarkedPtr<usize>, guard: &Guard) -> bool {
+    match unsafe { SlotWord::decode(word) } {
+        SlotWord::Descr(BaseDescr::PopDescrType(pop)) => pop.child(guard).is_some(),
+        _ => false,
     }
-    let rawstate: u8 = unsafe { mystate.deref() }.clone();
+}
 
-    (mystate, rawstate)
+pub fn loadstate(newdescr: &PushDescr) -> u8 {
+    newdescr.state.load(Acquire)
 }
 
-pub fn value_base(descr: BaseDescr) -> Option<usize> {
+pub(crate) fn value_base(descr: &BaseDescr) -> Option<ValuePtr> {
     match descr {
         BaseDescr::PushDescrType(d) => Some(d.value),
-        BaseDescr::PopDescrType(d) => None, // NOTE: C++ Version returns a NotValue instead
-        BaseDescr::PopSubDescrType(d) => Some(d.value),
-        _ => None,
+        BaseDescr::PopDescrType(_d) => None, // NOTE: C++ Version returns a NotValue instead
+        BaseDescr::PopSubDescrType(d) => d.value,
+        BaseDescr::WriteDescrType(d) => Some(d.old),
+        BaseDescr::MultiDescrType(d) => Some(d.value()),
     }
 }
 
 #[derive(Clone)]
 pub enum BaseOp {
-    PushOpType(PushOp),
+    PushOpType(Arc<PushOp>),
     PopOpType(Arc<PopOp>),
-    WriteOpType(WriteOp),
+    WriteOpType(Arc<WriteOp>),
+    // A cas_multi is its own result, helpers finish the same descriptor.
+    MultiOpType(Arc<MultiDescr>),
 }
 
-#[derive(Clone)]
+// Where an announced push put its value, and which of the PushDescrs that
+// helpers placed for it got to do so. `pos` is None and `by` 0 if the vector
+// was already at the maximum its GrowthPolicy allows, or for a push at a
+// given position if the value could not go there.
+pub struct PushResult {
+    pos: Option<usize>,
+    by: usize,
+}
+
+// Every descriptor placed for the op carries the same boxed value, so it
+// ends up in the vector once, through the descriptor recorded in `result`.
 pub struct PushOp {
-    done: Atomic<AtomicBool>,
-    value: usize,
-    result: Atomic<AtomicUsize>,
-    can_return: Atomic<AtomicBool>,
+    value: ValuePtr,
+    // The position the value has to go to, for try_push_at.
+    at: Option<usize>,
+    result: AtomicMarkablePtr<PushResult>,
 }
 
 impl PushOp {
-    pub fn new(value: usize) -> PushOp {
+    pub(crate) fn new(value: ValuePtr, at: Option<usize>) -> PushOp {
         PushOp {
-            done: Atomic::new(AtomicBool::new(false)),
             value,
-            result: Atomic::new(AtomicUsize::new(NO_RESULT)),
-            can_return: Atomic::new(AtomicBool::new(false)),
+            at,
+            result: AtomicMarkablePtr::null(),
         }
     }
 }
 
-#[derive(Clone)]
+impl Drop for PushOp {
+    fn drop(&mut self) {
+        let result = self.result.load(Relaxed, unsafe { epoch::unprotected() });
+        if !result.is_null() {
+            drop(unsafe { result.into_box() });
+        }
+    }
+}
+
+// What an announced pop ended with, and which of the PopDescrs that helpers
+// placed for it got to take the value. `by` is 0 if the vector was empty.
+pub struct PopResult {
+    value: Option<ValuePtr>,
+    by: usize,
+}
+
 pub struct PopOp {
-    done: Atomic<bool>,
-    result: Atomic<Option<usize>>,
+    result: AtomicMarkablePtr<PopResult>,
+}
+
+impl Default for PopOp {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PopOp {
     pub fn new() -> PopOp {
         PopOp {
-            done: Atomic::new(false),
-            result: Atomic::null(),
+            result: AtomicMarkablePtr::null(),
         }
     }
 }
 
-#[derive(Clone)]
+impl Drop for PopOp {
+    fn drop(&mut self) {
+        let result = self.result.load(Relaxed, unsafe { epoch::unprotected() });
+        if !result.is_null() {
+            drop(unsafe { result.into_box() });
+        }
+    }
+}
+
+// What a cwrite expects to find in its spot: a value equal to the boxed one,
+// compared with the function next to it so completing a cwrite does not need
+// T: PartialEq, or whichever value has the given version.
+#[derive(Clone, Copy)]
+pub(crate) enum Expected {
+    Value(ValuePtr, fn(ValuePtr, ValuePtr) -> bool),
+    Version(u64),
+}
+
+impl Expected {
+    // `current` must not have been freed yet.
+    unsafe fn matches(self, current: ValuePtr) -> bool {
+        match self {
+            Expected::Value(old, same) => same(old, current),
+            Expected::Version(version) => current.version() == version,
+        }
+    }
+}
+
+// What an announced cwrite ended with. Either `by` is the WriteDescr that
+// wrote and `found` the value it replaced, or `by` is 0 and `found` the value
+// that did not match, None if there was none.
+pub struct WriteResult {
+    by: usize,
+    found: Option<ValuePtr>,
+}
+
+// The boxed values in `expected` and `new` belong to the op and are freed
+// with it, helpers install copies of `new` of their own, made with `copy`
+// so that helping does not need T: Clone.
 pub struct WriteOp {
     pos: usize,
     // done: Atomic<AtomicBool>,
-    old: usize,
-    new: usize,
-    result: Arc<Atomic<Option<bool>>>,
+    expected: Expected,
+    new: ValuePtr,
+    copy: unsafe fn(ValuePtr, u64) -> ValuePtr,
+    result: AtomicMarkablePtr<WriteResult>,
 }
 
 impl WriteOp {
-    pub fn new(pos: usize, old: usize, new: usize) -> WriteOp {
+    pub(crate) fn new<T: Clone>(pos: usize, expected: Expected, new: ValuePtr) -> WriteOp {
         WriteOp {
             // done: Atomic::new(AtomicBool::new(false)),
-            result: Arc::new(Atomic::new(None)),
+            result: AtomicMarkablePtr::null(),
             pos,
-            old,
+            expected,
             new,
+            copy: ValuePtr::copy::<T>,
+        }
+    }
+}
+
+impl Drop for WriteOp {
+    fn drop(&mut self) {
+        let result = self.result.load(Relaxed, unsafe { epoch::unprotected() });
+        if !result.is_null() {
+            drop(unsafe { result.into_box() });
+        }
+    }
+}
+
+// Placed by a helper of an announced cwrite over the value `old` it found,
+// to swap it for `new`, a copy of the op's value of its own. Of several
+// helpers that get this far only the first to record itself in the op
+// writes, the others put `old` back, so the op writes at most once.
+pub struct WriteDescr {
+    owner: Arc<WriteOp>,
+    old: ValuePtr,
+    new: ValuePtr,
+}
+
+// One position of a cas_multi: the value expected there, the value to put
+// in its place, and the address of the MultiSlotDescr that holds the spot
+// for the cas_multi, 0 until one does.
+struct MultiEntry {
+    pos: usize,
+    old: ValuePtr,
+    new: ValuePtr,
+    installed: AtomicUsize,
+}
+
+// A cas_multi, shared by everyone who helps it. Helpers take its positions
+// over one after the other in ascending order, each with a MultiSlotDescr,
+// and once every position is held it PASSED. A position that holds anything
+// but its expected value makes it FAILED. Then every slot gives way to the
+// new value or hands back the one it took.
+//
+// Going in order means that of two cas_multis that want the same positions
+// the one holding the lowest of them never waits for the other, so helping
+// each other always ends.
+pub struct MultiDescr {
+    entries: Vec<MultiEntry>,
+    same: fn(ValuePtr, ValuePtr) -> bool,
+    // The boxed values belong to the descriptor, except for the new ones
+    // once it PASSED, and this frees them without knowing T.
+    free: unsafe fn(ValuePtr),
+    state: AtomicU8,
+}
+
+impl MultiDescr {
+    // `entries` are (position, expected, new) sorted by position.
+    pub(crate) fn new<T>(entries: Vec<(usize, ValuePtr, ValuePtr)>, same: fn(ValuePtr, ValuePtr) -> bool) -> MultiDescr {
+        MultiDescr {
+            entries: entries.into_iter().map(|(pos, old, new)| MultiEntry { pos, old, new, installed: AtomicUsize::new(0) }).collect(),
+            same,
+            free: ValuePtr::free::<T>,
+            state: AtomicU8::new(STATE_UNDECIDED),
+        }
+    }
+}
+
+impl Drop for MultiDescr {
+    fn drop(&mut self) {
+        let passed = self.state.load(Relaxed) == STATE_PASSED;
+
+        for entry in &self.entries {
+            unsafe {
+                (self.free)(entry.old);
+                if !passed {
+                    (self.free)(entry.new);
+                }
+            }
         }
     }
 }
 
+// Placed by a helper of a cas_multi over the value `found` at the position
+// of entry `index`. Only the slot the entry records holds the position for
+// the cas_multi. Any other came in after the cas_multi was decided and just
+// hands `found` back.
+pub struct MultiSlotDescr {
+    parent: Arc<MultiDescr>,
+    index: usize,
+    found: ValuePtr,
+}
+
+impl MultiSlotDescr {
+    fn entry(&self) -> &MultiEntry {
+        &self.parent.entries[self.index]
+    }
+
+    // Whether this slot swaps `found` for the new value of its entry.
+    fn writes(&self) -> bool {
+        let recorded = self.entry().installed.load(Acquire) == ptr::from_ref(self).addr();
+        recorded && self.parent.state.load(Acquire) == STATE_PASSED
+    }
+
+    // The value the position holds as far as readers are concerned.
+    fn value(&self) -> ValuePtr {
+        if self.writes() { self.entry().new } else { self.found }
+    }
+}
+
+// Sets the result of an op unless a helper already did. Every helper that
+// gets to decide the op offers one, only the first is kept.
+fn record<R>(slot: &AtomicMarkablePtr<R>, result: R, guard: &Guard) {
+    let new = MarkedPtr::from_box(Box::new(result));
+    if slot.compare_exchange(MarkedPtr::null(), new, AcqRel, Acquire, guard).is_err() {
+        drop(unsafe { new.into_box() });
+    }
+}
+
+// Frees an op that was taken out of the announcement table together with
+// the values it owns. A push that never got placed still owns its value.
+fn is_placed(op: &PushOp, guard: &Guard) -> bool {
+    let result = op.result.load(Acquire, guard);
+    !result.is_null() && unsafe { result.deref() }.pos.is_some()
+}
+
+unsafe fn retire_op<T>(opptr: MarkedPtr<BaseOp>, guard: &Guard) {
+    match opptr.deref() {
+        BaseOp::PushOpType(op) if !is_placed(op, guard) => {
+            op.value.retire::<T>(guard);
+        },
+        BaseOp::WriteOpType(op) => {
+            if let Expected::Value(old, _) = op.expected {
+                old.retire::<T>(guard);
+            }
+            op.new.retire::<T>(guard);
+        },
+        _ => (),
+    }
+
+    opptr.defer_destroy(guard);
+}
+
+
+// Where the spots of a vector live. Contiguous storage is a single array
+// that gets replaced by a bigger one on resize, segmented storage grows by
+// adding buckets and never moves a spot.
+enum Storage {
+    Contiguous(AtomicMarkablePtr<Contiguous>),
+    Segmented(Segmented),
+}
+
+// What read_range and snapshot_range found at each position of their range.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RangeSlot {
+    // The value went into the matching element of the output slice.
+    Read,
+    // Below the length but nothing there, a pop got to it while we read.
+    Empty,
+    // At or past the length, the output element was left alone.
+    BeyondSize,
+}
 
-pub struct WaitFreeVector {
-    storage: Atomic<Contiguous>,
-    size: Atomic<AtomicUsize>,
+// Elements are owned by the vector: each one is dropped exactly once, when a
+// pop takes it, when a cwrite replaces it or when the vector is dropped.
+// Reads hand out clones, since another thread may pop the element right after.
+pub struct WaitFreeVector<T = usize> {
+    storage: Storage,
+    // Every push and pop reads and updates the size. Padded, it is kept off
+    // the line of `storage`, which only changes on resize.
+    size: Padded<Size>,
 
     thread_ops: Vec<OpSpot>,
-    thread_to_help: Vec<AtomicUsize>,
+    // Written by its own thread on every operation, padded along with the
+    // announcement table.
+    thread_to_help: Vec<Padded<AtomicUsize>>,
     num_threads: usize,
+
+    // How many versions each thread has handed out, see stamp.
+    stamps: Vec<CachePadded<AtomicU64>>,
+
+    // Indexed by tid like the announcement table. A thread that finds its
+    // pool busy, because another one is using its tid, allocates instead.
+    // Padded too, since taking the lock writes to it.
+    pools: Vec<CachePadded<Mutex<DescrPool>>>,
+
+    // Only a fixed vector has these: epochs of its own for what its pools
+    // hand out again, and per tid the boxes its values go in. Everything
+    // else a fixed vector retires still goes through epoch.rs.
+    reclaim: Option<Arc<Reclaim>>,
+    values: Vec<CachePadded<Mutex<ValuePool>>>,
+
+    growth: GrowthPolicy,
+
+    elements: PhantomData<T>,
 }
 
-impl WaitFreeVector {
-    pub fn new(capacity: usize, num_threads: usize) -> WaitFreeVector {
+impl<T: Send + Sync + 'static> WaitFreeVector<T> {
+    pub fn new(capacity: usize, num_threads: usize) -> WaitFreeVector<T> {
+        WaitFreeVector::with_growth_policy(capacity, num_threads, GrowthPolicy::Double)
+    }
+
+    pub fn new_segmented(capacity: usize, num_threads: usize) -> WaitFreeVector<T> {
+        WaitFreeVector::with_storage(Storage::Segmented(Segmented::new(capacity)), num_threads, GrowthPolicy::Double)
+    }
+
+    // A vector that holds at most `capacity` elements and never resizes: its
+    // spots are allocated here, once, and each thread gets a pool of push and
+    // pop descriptors. Pushes past the end fail with try_push_back.
+    pub fn bounded(capacity: usize, num_threads: usize) -> WaitFreeVector<T> {
+        WaitFreeVector::with_growth_policy(capacity, num_threads, GrowthPolicy::Fixed(capacity))
+    }
+
+    // A contiguous vector that grows according to `growth` once `capacity`
+    // is used up.
+    pub fn with_growth_policy(capacity: usize, num_threads: usize, growth: GrowthPolicy) -> WaitFreeVector<T> {
+        let contig = Contiguous::new(growth.initial_capacity(capacity));
+        WaitFreeVector::with_storage(Storage::Contiguous(AtomicMarkablePtr::new(contig)), num_threads, growth)
+    }
+
+    fn with_storage(storage: Storage, num_threads: usize, growth: GrowthPolicy) -> WaitFreeVector<T> {
         let mut thread_ops: Vec<OpSpot> = Vec::new();
-        let mut thread_to_help: Vec<AtomicUsize> = Vec::new();
+        let mut thread_to_help: Vec<Padded<AtomicUsize>> = Vec::new();
         // let thread_to_help = vec![0; num_threads];
+        let preallocated = if growth.is_fixed() { POOL_SIZE } else { 0 };
+        let pools = (0..num_threads).map(|_| CachePadded::new(Mutex::new(DescrPool::new(preallocated)))).collect();
+
+        let reclaim = growth.is_fixed().then(|| Arc::new(Reclaim::new(num_threads)));
+        let values = match reclaim {
+            Some(_) => (0..num_threads).map(|_| CachePadded::new(Mutex::new(ValuePool::new::<T>(POOL_SIZE)))).collect(),
+            None => Vec::new(),
+        };
+
+        let stamps = (0..num_threads).map(|_| CachePadded::new(AtomicU64::new(0))).collect();
 
         for _ in 0..num_threads {
-            let i: Shared<BaseOp> = Shared::null();
+            let i: MarkedPtr<BaseOp> = MarkedPtr::null();
             thread_ops.push(make_op_spot(i));
 
             let i: AtomicUsize = AtomicUsize::new(0);
-            thread_to_help.push(i);
+            thread_to_help.push(padded(i));
         }
 
         WaitFreeVector{
-            storage: Atomic::new(Contiguous::new(capacity)),
-            size: Atomic::new(AtomicUsize::new(0)),
+            storage,
+            size: padded(Size::new()),
 
             thread_ops,
             thread_to_help,
             num_threads,
+
+            stamps,
+
+            pools,
+
+            reclaim,
+            values,
+
+            growth,
+
+            elements: PhantomData,
         }
     }
 
     pub fn length(&self) -> usize{
-        let guard = &epoch::pin();
-        let shsize = self.size.load(SeqCst, guard);
-        let sizeusizeptr = unsafe { shsize.deref() }.clone();
-        sizeusizeptr.load(SeqCst)
+        self.size.load()
     }
 
     pub fn help_if_needed(&self, tid: usize) {
@@ -203,17 +648,18 @@ impl WaitFreeVector {
             panic!("tid {} out of bounds for {} threads", tid, self.num_threads);
         }
 
-        let help = self.thread_to_help[tid].load(Acquire);
+        // Only the thread that is `tid` touches its own counter.
+        let help = self.thread_to_help[tid].load(Relaxed);
 
-        self.thread_to_help[tid].store((help + 1) % self.num_threads, Release);
+        self.thread_to_help[tid].store((help + 1) % self.num_threads, Relaxed);
 
         self.help(tid, help);
     }
 
     pub fn help(&self, mytid: usize, help: usize) {
-        let guard = &epoch::pin();
+        let guard = &self.pin(mytid);
 
-        let opptr = self.thread_ops[help].load(SeqCst, guard);
+        let opptr = self.thread_ops[help].load(Acquire, guard);
 
         if opptr.is_null() {
             return;
@@ -221,138 +667,356 @@ impl WaitFreeVector {
 
         self.an_complete_base(mytid, opptr, guard);
 
-        self.thread_ops[help].compare_and_set(opptr, Shared::null(), SeqCst, guard);
+        if self.thread_ops[help].compare_exchange(opptr, MarkedPtr::null(), AcqRel, Acquire, guard).is_ok() {
+            unsafe { retire_op::<T>(opptr, guard) };
+        }
     }
 
-    pub fn get_spot(&self, position: usize, guard: &Guard) -> Spot {
-        let contigptr = self.storage.load(SeqCst, guard);
-        let contig = unsafe { contigptr.deref() };
+    // The spare spot a bounded vector keeps for pops past its limit is not
+    // counted, nothing is ever stored there.
+    pub fn capacity(&self) -> usize {
+        let guard = &epoch::pin();
+        let capacity = match &self.storage {
+            Storage::Contiguous(storage) => unsafe { storage.load(Acquire, guard).deref() }.capacity,
+            Storage::Segmented(storage) => storage.capacity(guard),
+        };
+
+        capacity.min(self.growth.limit())
+    }
+
+    // The spot at `position`, growing the storage to reach it if need be.
+    // None if the GrowthPolicy never lets the storage get that big.
+    pub fn get_spot(&self, position: usize, guard: &Guard) -> Option<Spot> {
+        self.in_reach(position).then(|| self.spot_at(position, guard))
+    }
+
+    // Like get_spot, together with the word the spot holds, see load_at.
+    pub fn load_spot<'g>(&self, position: usize, guard: &'g Guard) -> Option<(Spot, MarkedPtr<'g, usize>)> {
+        self.in_reach(position).then(|| self.load_at(position, guard))
+    }
+
+    // Whether the storage may ever hold a spot at `position`: one past the
+    // limit for a pop on a full vector, see GrowthPolicy::clamp.
+    fn in_reach(&self, position: usize) -> bool {
+        position < self.growth.clamp(usize::MAX)
+    }
+
+    // What get_spot does for positions the vector itself asks for, which
+    // are always in reach.
+    fn spot_at(&self, position: usize, guard: &Guard) -> Spot {
+        debug_assert!(self.in_reach(position), "position {} is past the maximum capacity", position);
+
+        let storage = match &self.storage {
+            Storage::Contiguous(storage) => storage,
+            Storage::Segmented(storage) => return storage.get_spot(position, guard),
+        };
+
+        loop {
+            let contigptr = storage.load(Acquire, guard);
+            let contig = unsafe { contigptr.deref() };
+
+            // Every thread that touches the vector while a resize is migrating
+            // copies one chunk, so no single thread pays for the whole copy.
+            contig.help_migrate(guard);
+
+            if position < contig.capacity {
+                return contig.get_spot(position, guard);
+            }
+
+            // Every round replaces the generation we saw, whether with ours or
+            // with the one somebody else announced on it first.
+            let new_capacity = self.growth.next_capacity(contig.capacity, position + 1);
+            self.install(storage, contigptr, new_capacity, guard);
+        }
+    }
+
+    // Loads the word at `position`. A frozen word means either a newer
+    // generation is already installed or the spot was sealed by a pending
+    // shrink_to_fit, which we help finish. Then spot_at is retried until it
+    // hands out a live spot.
+    fn load_at<'g>(&self, position: usize, guard: &'g Guard) -> (Spot, MarkedPtr<'g, usize>) {
+        loop {
+            let spot = self.spot_at(position, guard);
+            let word = spot.load(Acquire, guard);
+
+            if !is_frozen(word) {
+                return (spot, word);
+            }
 
-        if position >= contig.capacity {
-            self.resize();
-            return self.get_spot(position, guard);
+            if let Storage::Contiguous(storage) = &self.storage {
+                self.help_shrink(storage, storage.load(Acquire, guard), guard);
+            }
         }
-        let spot = contig.get_spot(position, guard);
+    }
 
-        spot
+    // Number of Contiguous generations reachable from the vector: 1 once the
+    // latest resize has finished migrating, 2 while it is still copying.
+    pub fn generations(&self) -> usize {
+        let guard = &epoch::pin();
+        match &self.storage {
+            Storage::Contiguous(storage) => {
+                let contig = unsafe { storage.load(Acquire, guard).deref() };
+                if contig.old.load(Acquire, guard).is_null() { 1 } else { 2 }
+            },
+            Storage::Segmented(_) => 1,
+        }
     }
 
     pub fn resize(&self){
-        // println!("Resizing");
+        // Segmented storage allocates buckets on demand in spot_at.
+        let storage = match &self.storage {
+            Storage::Contiguous(storage) => storage,
+            Storage::Segmented(_) => return,
+        };
+
         let guard = &epoch::pin();
-        let old = self.storage.load(SeqCst, guard);
+        let current = storage.load(Acquire, guard);
 
-        let mut prefix = 0;
-        if !old.is_null() {
-            let old = unsafe {old.deref()};
-            prefix = old.capacity;
+        let prefix = unsafe { current.deref() }.capacity;
+        let new_capacity = self.growth.next_capacity(prefix, prefix + 1);
+
+        // A bounded vector that is already as big as it gets.
+        if new_capacity > prefix {
+            self.install(storage, current, new_capacity, guard);
+        }
+    }
+
+    // Replaces the generation `current` with a new one of `new_capacity`
+    // spots that migrates from it. Returns false if another resize or shrink
+    // of `current` got to announce its generation first, which then replaces
+    // `current` instead.
+    fn install(&self, storage: &AtomicMarkablePtr<Contiguous>, current: MarkedPtr<Contiguous>, new_capacity: usize, guard: &Guard) -> bool {
+        let contig = unsafe { current.deref() };
+
+        let mut next = contig.next.load(Acquire, guard);
+        let mut ours = false;
+
+        if next.is_null() {
+            let v_new = Contiguous::from_old(current, new_capacity);
+            let v_new = MarkedPtr::from_box(Box::new(v_new));
+            match contig.next.compare_exchange(MarkedPtr::null(), v_new, AcqRel, Acquire, guard) {
+                Ok(_) => {
+                    next = v_new;
+                    ours = true;
+                },
+                Err(current) => {
+                    // Nobody else has seen the generation we built, free it right away.
+                    drop(unsafe { v_new.into_box() });
+                    next = current;
+                },
+            }
         }
 
-        let new_capacity = prefix * 2 + 1;
+        self.complete_install(storage, current, next, guard);
+
+        ours
+    }
 
-        let mut arr: Vec<Spot> = Vec::with_capacity(new_capacity);
-        for i in 0..new_capacity {
-            if i < prefix {
-                let init: Shared<usize> = Shared::null().with_tag(TagNotCopied);
-                arr.push(Arc::new(Atomic::from(init)));
+    // Puts `next`, the generation announced on `current`, in its place. Any
+    // thread that finds it announced can do this, so a resize never waits
+    // for the thread that started it, and all resizes racing on `current`
+    // end up installing the same generation.
+    fn complete_install(&self, storage: &AtomicMarkablePtr<Contiguous>, current: MarkedPtr<Contiguous>, next: MarkedPtr<Contiguous>, guard: &Guard) {
+        // The current generation has to be fully migrated before it can be
+        // replaced. That keeps the chain at most two generations long, so
+        // copy_value never has to recurse and old generations get retired.
+        unsafe { current.deref() }.migrate(guard);
+
+        if storage.compare_exchange(current, next, AcqRel, Acquire, guard).is_ok() {
+            let newv = unsafe { next.deref() };
+            if newv.prefix == 0 {
+                // Nothing to copy, so nothing will ever retire `current`.
+                Contiguous::retire(current, guard);
+            } else {
+                newv.help_migrate(guard);
             }
-            else {
-                let init: Shared<usize> = Shared::null().with_tag(TagNotValue);
-                arr.push(Arc::new(Atomic::from(init)));
+        }
+    }
+
+    // Grows the storage so that at least `additional` more elements fit
+    // without a resize. Unlike repeated doubling this migrates only once.
+    pub fn reserve(&self, tid: usize, additional: usize) {
+        self.help_if_needed(tid);
+
+        let guard = &self.pin(tid);
+        let target = self.growth.clamp(self.length() + additional);
+
+        let storage = match &self.storage {
+            Storage::Contiguous(storage) => storage,
+            Storage::Segmented(storage) => return storage.reserve(target, guard),
+        };
+
+        loop {
+            let current = storage.load(Acquire, guard);
+            if unsafe { current.deref() }.capacity >= target {
+                return;
+            }
+
+            if self.install(storage, current, target, guard) {
+                return;
             }
         }
+    }
 
-        let old_atomic = self.storage.clone();
+    // Replaces the storage with one that is only as big as the vector, plus
+    // the spot right above the last element, where a pop places its
+    // descriptor; without it the next pop would grow the storage right
+    // back. The spots past the new capacity get sealed first; if a
+    // concurrent push got there before us the shrink is abandoned and false
+    // is returned.
+    // Segmented storage never moves its buckets and is left alone, and
+    // neither is the storage of a fixed vector.
+    pub fn shrink_to_fit(&self, tid: usize) -> bool {
+        self.help_if_needed(tid);
 
-        let v_new = Contiguous{
-            old: old_atomic,
-            capacity: new_capacity,
-            array: Atomic::new(arr),
+        let guard = &self.pin(tid);
+        let storage = match &self.storage {
+            Storage::Contiguous(storage) if !self.growth.is_fixed() => storage,
+            _ => return false,
         };
 
-        let shared_cont = self.storage.load(SeqCst, guard);
+        let current = storage.load(Acquire, guard);
+        let contig = unsafe { current.deref() };
+        let target = self.length() + 1;
+
+        if target >= contig.capacity {
+            return false;
+        }
+
+        let shrink = MarkedPtr::from_box(Box::new(Shrink::new(target)));
+        if contig.shrink.compare_exchange(MarkedPtr::null(), shrink, AcqRel, Acquire, guard).is_err() {
+            // Somebody else is already shrinking this generation.
+            drop(unsafe { shrink.into_box() });
+            return false;
+        }
+
+        self.help_shrink(storage, current, guard);
+
+        storage.load(Acquire, guard) != current && self.capacity() <= target
+    }
+
+    // Drives the pending shrink of `current` to its end: seal the spots past
+    // the target, then either install the smaller generation or undo the seals.
+    // Any thread can run this, so a preempted shrinker never blocks a push.
+    fn help_shrink(&self, storage: &AtomicMarkablePtr<Contiguous>, current: MarkedPtr<Contiguous>, guard: &Guard) {
+        let contig = unsafe { current.deref() };
+        let shrinkptr = contig.shrink.load(Acquire, guard);
+        if shrinkptr.is_null() {
+            return;
+        }
+
+        let shrink = unsafe { shrinkptr.deref() };
+        let seal = shrink.seal_word(shrinkptr);
+        let vec = unsafe { contig.array.load(Acquire, guard).deref() };
+
+        // Seals and state stay SeqCst. A sealer CASes a spot and then reads
+        // the state, whoever gives up CASes the state and then undoes the
+        // seals. Anything weaker and each could miss the other's write,
+        // leaving a seal of an abandoned shrink behind for good.
+        if shrink.state.load(SeqCst) == STATE_UNDECIDED {
+            for spot in vec.iter().skip(shrink.target) {
+                let mut word = spot.load(Acquire, guard);
+
+                while word != seal && shrink.state.load(SeqCst) == STATE_UNDECIDED {
+                    let empty = matches!(unsafe { SlotWord::decode(word) }, SlotWord::NotValue);
+                    if !empty && !is_spent_pop(word, guard) {
+                        let _ = shrink.state.compare_exchange(STATE_UNDECIDED, STATE_FAILED, SeqCst, SeqCst);
+                        break;
+                    }
 
-        match self.storage.compare_and_set(
-            shared_cont, Owned::new(v_new), SeqCst,guard) {
-            Ok(_) => {
-                let shared_newv = self.storage.load(SeqCst, guard);
-                let newv = unsafe {shared_newv.deref()};
-                for i in 0..new_capacity{
-                    newv.copy_value(i, guard);
+                    match spot.compare_exchange(word, seal, SeqCst, SeqCst, guard) {
+                        Ok(_) => {
+                            // The seal took a spent pop out of the spot, so
+                            // nobody will ever replace it.
+                            unsafe { self.retire_descr(word, guard) };
+
+                            // Sealed too late: the shrink was already given up
+                            // and whoever undid the seals might have missed ours.
+                            if shrink.state.load(SeqCst) == STATE_FAILED {
+                                let _ = spot.compare_exchange(seal, MarkedPtr::null().with_mark(TagNotValue), SeqCst, SeqCst, guard);
+                            }
+                            break;
+                        },
+                        Err(current) => word = current,
+                    }
                 }
-                // println!("Resize {} ", new_capacity);
-            },
-            Err(_) => {
-                // println!("Resize Failed");
-            },
+            }
+
+            let _ = shrink.state.compare_exchange(STATE_UNDECIDED, STATE_PASSED, SeqCst, SeqCst);
         }
 
+        if shrink.state.load(SeqCst) == STATE_PASSED {
+            self.install(storage, current, shrink.target, guard);
+            return;
+        }
 
+        for spot in vec.iter().skip(shrink.target) {
+            let _ = spot.compare_exchange(seal, MarkedPtr::null().with_mark(TagNotValue), SeqCst, SeqCst, guard);
+        }
 
+        // Make room for another attempt on this generation.
+        if contig.shrink.compare_exchange(shrinkptr, MarkedPtr::null(), AcqRel, Acquire, guard).is_ok() {
+            unsafe { shrinkptr.defer_destroy(guard) };
+        }
     }
 
-    pub fn complete_base(&self, spot: Spot, old: Shared<usize>, descr: &BaseDescr, guard: &Guard) -> bool {
+    pub fn complete_base(&self, spot: Spot, old: MarkedPtr<usize>, descr: &BaseDescr, guard: &Guard) -> bool {
         // let cdescr = descr.clone();
         match descr {
             BaseDescr::PushDescrType(d) => self.complete_push(spot, old, d, guard),
             BaseDescr::PopDescrType(d) => self.complete_pop(spot, old, d.clone(), guard),
             BaseDescr::PopSubDescrType(d) => self.complete_pop_sub(spot, old, d.clone(), guard),
-            _ => false,
+            BaseDescr::WriteDescrType(d) => self.complete_write(old, d, guard),
+            BaseDescr::MultiDescrType(d) => self.complete_multi_slot(old, d, guard),
         }
     }
 
     // the an_ prefix means this method is to complete an op on the announcement table, not in a descriptor
-    pub fn an_complete_base(&self, tid: usize, opptr: Shared<BaseOp>, guard: &Guard) -> bool {
+    pub fn an_complete_base(&self, tid: usize, opptr: MarkedPtr<BaseOp>, guard: &Guard) -> bool {
         let op: &BaseOp = unsafe { opptr.deref() };
         match op {
             BaseOp::PushOpType(o) => self.an_complete_push(tid, o, opptr, guard),
             BaseOp::PopOpType(o) => self.an_complete_pop(tid, o, opptr, guard),
             BaseOp::WriteOpType(o) => self.an_complete_cwrite(tid, o, opptr, guard),
+            BaseOp::MultiOpType(o) => self.complete_multi(o, None, guard),
         }
     }
 
-    pub fn an_complete_cwrite(&self, tid: usize, op: &WriteOp, opptr: Shared<BaseOp>, guard: &Guard) -> bool {
-        let arcres = op.result.clone();
-        loop {   
-            let resptr = arcres.load(SeqCst, guard);
-            if resptr.is_null() {
-                return false;
-            }
-
-            let res = unsafe { resptr.deref() }.clone();
-            if let Some(_) = res {
-                return true;
-            }
-
-            let spot = self.get_spot(op.pos, guard);
-            let expected = spot.load(SeqCst, guard);
-
-            if let Some(x) = unpack_descr(expected, guard) {
-                let base = unsafe { x.deref() };
-                self.complete_base(spot, expected, base, guard);
-                continue;
-            }
-
-            if expected.tag() != TagNotValue || expected.is_null() {
-                return false;
-            }
+    pub fn an_complete_cwrite(&self, tid: usize, op: &Arc<WriteOp>, _opptr: MarkedPtr<BaseOp>, guard: &Guard) -> bool {
+        while op.result.load(Acquire, guard).is_null() {
+            let (spot, expected) = self.load_at(op.pos, guard);
 
-            let rawval = unsafe { expected.deref() }.clone();
+            let current = match unsafe { SlotWord::decode(expected) } {
+                SlotWord::Descr(base) => {
+                    self.complete_base(spot, expected, base, guard);
+                    continue;
+                },
+                SlotWord::Value(current) if unsafe { op.expected.matches(current) } => current,
+                other => {
+                    let found = match other {
+                        SlotWord::Value(value) => Some(value),
+                        _ => None,
+                    };
+                    let failed = WriteResult { by: 0, found };
+                    record(&op.result, failed, guard);
+                    break;
+                },
+            };
 
-            if rawval != op.old {
-                let new = Owned::new(Some(false));
-                arcres.compare_and_set(resptr, new, SeqCst, guard);
+            // The op keeps `new` to itself so it can free it without looking
+            // at the vector.
+            let copy = unsafe { (op.copy)(op.new, self.stamp(tid)) };
+            let descr = BaseDescr::WriteDescrType(Arc::new(WriteDescr { owner: op.clone(), old: current, new: copy }));
+            let descrptr = pack_descr(descr.clone(), guard);
 
-                return true;
+            if spot.compare_exchange(expected, descrptr, AcqRel, Acquire, guard).is_err() {
+                unsafe {
+                    drop_descr(descrptr, guard);
+                    copy.free::<T>();
+                }
             }
             else {
-                let newptr = Owned::new(op.new);
-                if let Ok(_) = spot.compare_and_set(expected, newptr, SeqCst, guard) {
-                    let newres = Owned::new(Some(true));
-                    arcres.compare_and_set(resptr, newres, SeqCst, guard);
-                }
-
-                return true;
+                self.complete_base(spot, descrptr, &descr, guard);
             }
         }
 
@@ -360,562 +1024,1037 @@ impl WaitFreeVector {
     }
 
     // the an_ prefix means this method is to complete an op on the announcement table, not in a descriptor
-    pub fn an_complete_push(&self, tid: usize, op: &PushOp, opptr: Shared<BaseOp>, guard: &Guard) -> bool {
-        let shsize = self.size.load(SeqCst, guard);
-        let usizeptr = unsafe { shsize.deref() }.clone();
-        let mut pos = usizeptr.load(SeqCst);
-
-        loop {
-            let spot = self.get_spot(pos, guard);
-            let expected = spot.load(SeqCst, guard);
-
-            let doneptr = op.done.load(SeqCst, guard);
-            let done = unsafe { doneptr.deref() };
-            let rawdone = done.load(SeqCst);
+    pub fn an_complete_push(&self, _tid: usize, op: &Arc<PushOp>, _opptr: MarkedPtr<BaseOp>, guard: &Guard) -> bool {
+        let mut pos = op.at.unwrap_or_else(|| self.size.load());
+        let give_up = || {
+            let nowhere = PushResult { pos: None, by: 0 };
+            record(&op.result, nowhere, guard);
+        };
 
-            if rawdone {
+        while op.result.load(Acquire, guard).is_null() {
+            // A value sits right below `pos`, so the vector is full. A push
+            // at `at` also gives up once the length is anything else, see
+            // push().
+            if pos >= self.growth.limit() || op.at.is_some_and(|at| at != self.size.load()) {
+                give_up();
                 break;
             }
 
-            // let expected = spot.load(SeqCst, guard);
+            let (spot, expected) = self.load_at(pos, guard);
 
-            if let Some(x) = unpack_descr(expected, guard) {
-                let base = unsafe { x.deref() };
-                self.complete_base(spot, expected, base, guard);
-                continue;
-            }
-
-            if expected.tag() != TagNotValue {
-                pos += 1;
-                continue;
+            match unsafe { SlotWord::decode(expected) } {
+                SlotWord::NotValue => (),
+                SlotWord::Descr(base) => {
+                    self.complete_base(spot, expected, base, guard);
+                    continue;
+                },
+                _ if op.at.is_some() => {
+                    give_up();
+                    break;
+                },
+                _ => {
+                    pos += 1;
+                    continue;
+                },
             }
 
-            let pdescr = PushDescr::new(pos, op.value);
-            pdescr.owner.store(opptr, SeqCst);
-            let descr = BaseDescr::PushDescrType(pdescr);
+            let descr = BaseDescr::PushDescrType(Arc::new(PushDescr::with_owner(pos, op.clone())));
             let descrptr = pack_descr(descr.clone(), guard);
 
-            if let Ok(_) = spot.compare_and_set(expected, descrptr, SeqCst, guard) {
-                let completeres = self.complete_base(spot, descrptr, &descr, guard);
-
-                if completeres {
-                    let resptr = op.result.load(SeqCst, guard);
-                    let res = unsafe { resptr.deref() };
-                    res.store(pos, SeqCst);
-
-                    usizeptr.fetch_add(1, SeqCst);
-
-                    let dptr = op.done.load(SeqCst, guard);
-                    let done = unsafe { dptr.deref() };
-                    done.store(true, SeqCst);
-
-                    let retptr = op.can_return.load(SeqCst, guard);
-                    let ret = unsafe { retptr.deref() };
-                    ret.store(true, SeqCst);
+            if spot.compare_exchange(expected, descrptr, AcqRel, Acquire, guard).is_err() {
+                unsafe { drop_descr(descrptr, guard) };
+            }
+            // A descriptor that failed found the spot below empty. One that
+            // passed but lost to another of the op finds the result set.
+            else if !self.complete_base(spot, descrptr, &descr, guard) {
+                if op.at.is_some() {
+                    give_up();
                 }
                 else {
-                    if pos == 0 {
-                        pos += 1;
-                    }
-                    else {
-                        pos -= 1;
-                    }
+                    pos = pos.saturating_sub(1);
                 }
             }
-            // self.get_spot(pos, guard: &Guard)
         }
 
-        loop {
-            let retptr = op.can_return.load(SeqCst, guard);
-            let ret = unsafe { retptr.deref() };
-            if ret.load(SeqCst) {
-                break;
-            }
-            std::thread::sleep(std::time::Duration::from_millis(3));
+        // The descriptor that pushed the value may still be in its spot, and
+        // the push is only visible once it is gone.
+        if let Some(pos) = unsafe { op.result.load(Acquire, guard).deref() }.pos {
+            self.settle(pos, guard);
         }
 
         true
     }
 
-    fn get_pos(&self, guard: &Guard) -> usize {
-        let shsize = self.size.load(SeqCst, guard);
-        let usizeptr = unsafe { shsize.deref() }.clone();
-        usizeptr.load(SeqCst)
-    }
-
-    pub fn an_complete_pop(&self, tid: usize, op: &PopOp, op_ptr: Shared<BaseOp>, guard: &Guard) -> bool {
-        while op.result.load(SeqCst, guard).is_null() {
-            let mut pos = self.get_pos(guard);
+    pub fn an_complete_pop(&self, _tid: usize, op: &Arc<PopOp>, _op_ptr: MarkedPtr<BaseOp>, guard: &Guard) -> bool {
+        let mut pos = self.size.load();
 
+        while op.result.load(Acquire, guard).is_null() {
             if pos == 0 {
-                op.result.store(Owned::new(None), SeqCst);
-                continue;
+                let empty = PopResult { value: None, by: 0 };
+                record(&op.result, empty, guard);
+                break;
             }
 
-            let spot = self.get_spot(pos, guard);
-            let expected = spot.load(SeqCst, guard);
-
-            if let Some(base_descr) = unpack_descr(expected, guard) {
-                let descr = unsafe { base_descr.deref() };
-                self.complete_base(spot, expected, descr, guard);
-                continue;
-            }
+            let (spot, expected) = self.load_at(pos, guard);
 
-            if expected.tag() != TagNotValue {
-                pos += 1;
-                continue;
+            match unsafe { SlotWord::decode(expected) } {
+                SlotWord::NotValue => (),
+                SlotWord::Descr(descr) => {
+                    self.complete_base(spot, expected, descr, guard);
+                    continue;
+                },
+                _ => {
+                    pos += 1;
+                    continue;
+                },
             }
 
-            let pop_descr = Rc::new(PopDescr::new(pos));
-            let cloned = pop_descr.clone();
-            pop_descr.owner.store(op_ptr, SeqCst);
+            let descr = BaseDescr::PopDescrType(Arc::new(PopDescr::with_owner(pos, op.clone())));
+            let packed_descr = pack_descr(descr.clone(), guard);
 
-
-            let packed_descr = pack_descr(BaseDescr::PopDescrType(pop_descr), guard);
-            if let Ok(old) = spot.compare_and_set(expected, packed_descr, SeqCst, guard) {
-                let descr = unsafe { unpack_descr(old, guard).expect("This should exist").deref() };
-                let res = self.complete_base(spot, old, descr, guard);
-                
-                if res {
-                    let child = unsafe { cloned.child.load(SeqCst, guard).deref() };
-                    let new_result = Owned::new(Some(child.value.clone()));
-                    let set_value = op.result.compare_and_set(Shared::null(), new_result, SeqCst, guard);
-                    
-                    assert!(set_value.is_ok());
-
-                    let usizeptr = unsafe { self.size.load(SeqCst, guard).deref() };
-
-                    usizeptr.fetch_sub(1, SeqCst);
-                } else {
-                    pos -= 1;
-                }
+            if spot.compare_exchange(expected, packed_descr, AcqRel, Acquire, guard).is_err() {
+                unsafe { drop_descr(packed_descr, guard) };
+            }
+            else if !self.complete_base(spot, packed_descr, &descr, guard) {
+                pos -= 1;
             }
         }
 
-        assert!(!op.result.load(SeqCst, guard).is_null());
-
         true
     }
 
-    pub fn cwrite(&self, tid: usize, pos: usize, old: usize, new: usize) -> bool {
-        self.help_if_needed(tid);
-        let guard = &epoch::pin();
+    // Settles `pos` if it is below the length. Anything at or past it is not
+    // there to read, and looking the spot up could make the storage grow.
+    fn read(&self, pos: usize, guard: &Guard) -> Option<ValuePtr> {
+        if pos >= self.size.load() {
+            return None;
+        }
 
-        let shsize = self.size.load(SeqCst, guard);
-        let sizeusizeptr = unsafe { shsize.deref() }.clone();
-        let size = sizeusizeptr.load(SeqCst);
+        self.settle(pos, guard)
+    }
 
-        if pos >= size {
-            return false;
-        }
+    // Loads the word at `position` and helps whatever descriptor sits there
+    // until the spot holds either a value, which is returned, or nothing.
+    // A spent pop descriptor leaves its spot empty and reads as nothing.
+    fn settle(&self, position: usize, guard: &Guard) -> Option<ValuePtr> {
+        loop {
+            let (spot, word) = self.load_at(position, guard);
 
-        for failures in 0..=LIMIT {
-            let spot: Spot = self.get_spot(pos, guard);
-            let oldptr = spot.load(SeqCst, guard);
-            match unpack_descr(oldptr, guard) {
-                Some(x) => {
-                    let descval = unsafe { x.deref() }.clone();
-                    self.complete_base(spot, oldptr, &descval, guard);
-                },
-                None => {
-                    if oldptr.tag() == TagNotValue || oldptr.is_null() {
-                        return false;
-                    }
+            if is_spent_pop(word, guard) {
+                return None;
+            }
 
-                    let realval = unsafe { oldptr.deref() }.clone();
-                    if realval == old {
-                        let res = spot.compare_and_set(oldptr, Owned::new(new), SeqCst, guard);
-                        match res {
-                            Ok(_) => {
-                                return true;
-                            },
-                            Err(_) => {
-                                return false;
-                            },
-                        }
-                    }
-                }
+            match unsafe { SlotWord::decode(word) } {
+                SlotWord::Descr(descr) => {
+                    self.complete_base(spot, word, descr, guard);
+                },
+                SlotWord::Value(value) => return Some(value),
+                _ => return None,
             }
         }
+    }
 
-        let op = WriteOp::new(pos, old, new);
-        let base_op = BaseOp::WriteOpType(op.clone());
-        self.announce_op(tid, Owned::new(base_op).into_shared(guard), guard);
+    // Reads every settled value from position 0 up to the first empty spot.
+    fn collect(&self, guard: &Guard) -> Vec<ValuePtr> {
+        let mut values = Vec::new();
 
-        let res = op.result.clone();
+        while values.len() < self.capacity() {
+            match self.settle(values.len(), guard) {
+                Some(value) => values.push(value),
+                None => break,
+            }
+        }
 
-        let rawresult = unsafe { res.load(SeqCst, guard).deref() }.clone();
+        values
+    }
+
+    // Settles every position of `range`. Positions past the capacity are
+    // reported empty rather than looked up, which would grow the storage.
+    fn collect_range(&self, range: Range<usize>, guard: &Guard) -> Vec<Option<ValuePtr>> {
+        let capacity = self.capacity();
+        range.map(|pos| if pos < capacity { self.settle(pos, guard) } else { None }).collect()
+    }
 
-        match rawresult {
-            Some(x) => x,
-            None => false,
+    // Panics if the vector is bounded and full, see try_push_back.
+    pub fn push_back(&self, tid: usize, value: T) {
+        if self.try_push_back(tid, value).is_err() {
+            panic!("push_back on a vector at its maximum capacity of {}", self.growth.limit());
         }
     }
 
-    pub fn at(&self, tid: usize, pos: usize) -> Option<usize> {
-        let guard = &epoch::pin();
+    // Pushes `value` unless the vector already holds as many elements as its
+    // GrowthPolicy allows, in which case it comes back, see push for when
+    // that is a copy.
+    pub fn try_push_back(&self, tid: usize, value: T) -> Result<(), CapacityExceeded<T>> {
+        self.push(tid, value, None).map_err(CapacityExceeded)
+    }
 
-        let shsize = self.size.load(SeqCst, guard);
-        let sizeusizeptr = unsafe { shsize.deref() }.clone();
-        let size = sizeusizeptr.load(SeqCst);
+    // Pushes `value` only if it becomes the element at `index`, that is if
+    // the vector holds exactly `index` elements once the push takes effect.
+    // Otherwise, or if the vector is at its maximum, it comes back, see push
+    // for when that is a copy.
+    pub fn try_push_at(&self, tid: usize, index: usize, value: T) -> Result<(), T> {
+        self.push(tid, value, Some(index))
+    }
 
-        if pos < size {
-            let slot = self.get_spot(pos, guard);
-            let ptr = slot.load(SeqCst, guard);
+    // What the pushes share. Without `at` the value goes to the first empty
+    // spot above the last value, with it a PushDescr goes to spot `at` only:
+    // a value there means the vector is longer, and the descriptor failing
+    // means the spot below is empty and the vector shorter.
+    //
+    // A value that fails to get pushed comes back as it was if none of our
+    // descriptors made it into a spot. Once one did, it comes back the way a
+    // popped value does, see ValuePtr::claim.
+    fn push(&self, tid: usize, value: T, at: Option<usize>) -> Result<(), T> {
+        self.help_if_needed(tid);
 
-            if ptr.tag() == TagNotValue || ptr.is_null() {
-                return None;
-            }
+        let guard = &self.pin(tid);
 
-            match unpack_descr(ptr, guard) {
-                Some(x) => {
-                    let descval = unsafe { x.deref() }.clone();
-                    return value_base(descval);
-                },
-                None => {
-                    return Some(unsafe { ptr.deref() }.clone());
-                },
-            }
+        // The length is the one thing a push at `at` can go by without
+        // touching spots, so a far off `at` never makes the storage grow.
+        let misplaced = |at: usize| at != self.size.load();
+        if at.is_some_and(misplaced) {
+            return Err(value);
         }
 
-        None
-    }
+        // Boxed once: whichever descriptor ends up pushing it installs this
+        // very box, and the others never make it reachable.
+        let value = self.box_value(tid, value);
+        let mut published = false;
+        let fail = |published: bool| Err(unsafe {
+            if published { self.take_value(tid, value, guard) } else { self.unbox_value(tid, value) }
+        });
 
-    pub fn push_back(&self, tid: usize, value: usize) {
-        self.help_if_needed(tid);
+        let mut pos = at.unwrap_or_else(|| self.size.load());
 
-        let guard = &epoch::pin();
+        for _failures in 0..=LIMIT {
+            // A value sits right below `pos`, so the vector is full.
+            if pos >= self.growth.limit() || at.is_some_and(misplaced) {
+                return fail(published);
+            }
 
-        let shvalue = Owned::new(value).into_shared(guard);
-
-        // if shvalue.is_null() {
-        //     panic!("CANNOT PUSH NULL POINTER");
-        // }
-
-        let shsize = self.size.load(SeqCst, guard);
-        let sizeusizeptr = unsafe { shsize.deref() }.clone();
-        let mut pos = sizeusizeptr.load(SeqCst);
-
-        for failures in 0..=LIMIT {
-            let spot = self.get_spot(pos, guard);
-            let expectedptr = spot.load(SeqCst, guard);
-            if expectedptr.tag() == TagNotValue
-            // || expectedptr.tag() == TagNotCopied
-            {
-                if pos == 0 {
-                    let res = spot.compare_and_set(expectedptr, shvalue, SeqCst, guard);
-                    match res {
-                        Ok(_) => {
-                            sizeusizeptr.fetch_add(1, SeqCst);
-                            return;
-                        },
-                        Err(_) => {
+            let (spot, expectedptr) = self.load_at(pos, guard);
+            if let SlotWord::NotValue = unsafe { SlotWord::decode(expectedptr) } {
+                let descr = self.push_descr(tid, pos, value);
+                let descrptr = pack_shared(descr.clone());
 
-                            pos += 1;
-                            continue;
-                        },
+                if spot.compare_exchange(expectedptr, descrptr, AcqRel, Acquire, guard).is_ok() {
+                    published = true;
+
+                    if self.complete_base(spot, descrptr, &descr, guard) {
+                        return Ok(());
+                    }
+                    else if at.is_some() {
+                        return fail(published);
+                    }
+                    else {
+                        pos -= 1;
                     }
                 }
-
-                let descr = BaseDescr::PushDescrType(PushDescr::new(pos, value));
-                let cdescr = descr.clone();
-                let descrptr = pack_descr(descr, guard);
-
-                match spot.compare_and_set(expectedptr, descrptr, SeqCst, guard) {
-                    Ok(_) => {
-                        if self.complete_base(spot, descrptr, &cdescr, guard) {
-                            sizeusizeptr.fetch_add(1, SeqCst);
-                            return;
-                        }
-                        else {
-                            pos -= 1;
-                        }
-                    },
-                    Err(_) => (),
+                else {
+                    unsafe { drop_descr(descrptr, guard) };
                 }
             }
             else {
-                // dbg!(expectedptr);
-                match unpack_descr(expectedptr, guard) {
-                    Some(x) => {
-                        let descr = unsafe { x.deref() }.clone();
-                        self.complete_base(spot, expectedptr, &descr, guard);
+                match unsafe { SlotWord::decode(expectedptr) } {
+                    SlotWord::Descr(descr) => {
+                        self.complete_base(spot, expectedptr, descr, guard);
                     }
-                    None => {
+                    _ if at.is_some() => {
+                        return fail(published);
+                    }
+                    _ => {
                         pos += 1;
                     }
                 }
             }
-            // let expected: usize = unsafe { spotptr.deref() }.clone();
         }
 
-        let op: Shared<BaseOp> = Owned::new(BaseOp::PushOpType(PushOp::new(value))).into_shared(guard);
+        let push_op = Arc::new(PushOp::new(value, at));
+        let op = MarkedPtr::from_box(Box::new(BaseOp::PushOpType(push_op.clone())));
+
+        self.announce_op(tid, op, guard);
 
-        self.announce_op(tid, op.clone(), guard);
+        // A push that did not happen leaves its value to the op, which we
+        // are still pinned for even if a helper already retired it. The op
+        // frees the box, and the value too unless we moved it out.
+        match unsafe { push_op.result.load(Acquire, guard).deref() }.pos {
+            Some(_) => Ok(()),
+            None => Err(unsafe { value.claim::<T>() }),
+        }
     }
 
-    pub fn announce_op(&self, tid: usize, op: Shared<BaseOp>, guard: &Guard) {
+    pub fn announce_op(&self, tid: usize, op: MarkedPtr<BaseOp>, guard: &Guard) {
         if tid >= self.num_threads {
             panic!("tid {} out of bounds for {} threads", tid, self.num_threads);
         }
 
-        let cur = self.thread_ops[tid].load(SeqCst, guard);
+        let cur = self.thread_ops[tid].load(Acquire, guard);
 
-        if !cur.is_null() {
-            println!("replacing existing op in annoucement table");
-        }
+        // Every announced op is out of the table again by the time announce_op
+        // returns, so one still there belongs to another thread using our tid.
+        debug_assert!(cur.is_null(), "tid {} announced an op while another was still in the table", tid);
 
-        self.thread_ops[tid].compare_and_set(cur, op, SeqCst, guard);
+        let _ = self.thread_ops[tid].compare_exchange(cur, op, AcqRel, Acquire, guard);
 
         self.help(tid, tid);
     }
 
-    pub fn complete_push(&self, spot: Spot, old: Shared<usize>, descr: &PushDescr, guard: &Guard) -> bool {
+    // A descriptor out of the pool of `tid`, or a new one if the pool is
+    // empty or another thread holds it.
+    fn push_descr(&self, tid: usize, pos: usize, value: ValuePtr) -> Arc<BaseDescr> {
+        match self.pools[tid].try_lock() {
+            Ok(mut pool) => pool.push_descr(pos, value, |stamp| self.is_safe(stamp)),
+            Err(_) => Arc::new(BaseDescr::PushDescrType(Arc::new(PushDescr::new(pos, value)))),
+        }
+    }
+
+    fn pop_descr(&self, tid: usize, pos: usize) -> Arc<BaseDescr> {
+        match self.pools[tid].try_lock() {
+            Ok(mut pool) => pool.pop_descr(pos, |stamp| self.is_safe(stamp)),
+            Err(_) => Arc::new(BaseDescr::PopDescrType(Arc::new(PopDescr::new(pos)))),
+        }
+    }
 
-        let newdescr = descr.clone();
+    fn is_safe(&self, stamp: usize) -> bool {
+        self.reclaim.as_ref().is_none_or(|reclaim| reclaim.is_safe(stamp))
+    }
 
-        let (mut mystate, mut rawstate) = loadstate(&newdescr, guard);
+    // Pins the epochs of epoch.rs, and on a fixed vector its own as well. Every
+    // operation that looks at spots runs under one of these.
+    fn pin(&self, tid: usize) -> Pinned<'_> {
+        Pinned::new(self.reclaim.as_deref(), tid)
+    }
 
-        if newdescr.pos == 0 {
-            if rawstate == STATE_UNDECIDED {
-                descr.state.compare_and_set(mystate, Owned::new(STATE_PASSED), SeqCst, guard);
+    // Retires the descriptor behind `word` like the free function does. A
+    // fixed vector stamps a descriptor its pool keeps instead and gives up
+    // the spot's reference right away, the pool holds on to it and waits
+    // for the stamp to be safe. Same contract as the free function.
+    unsafe fn retire_descr(&self, word: MarkedPtr<usize>, guard: &Guard) {
+        if let (Some(reclaim), Some(descr)) = (&self.reclaim, unpack_descr(word, guard)) {
+            if let Some(stamp) = pool::stamp_of(descr.deref()) {
+                stamp.store(reclaim.retire_epoch(), Release);
+                drop(release_descr(descr));
+                return;
             }
+        }
 
-            let basedescr = BaseDescr::PushDescrType(newdescr);
-            let maskdescr = pack_descr(basedescr, guard);
+        retire_descr(word, guard)
+    }
 
-            // NOTE: Do we need to check if this works or not?
-            spot.compare_and_set(old, maskdescr, SeqCst, guard);
+    // Boxes a value for thread `tid`, in a box out of its pool if the vector
+    // is fixed and the pool has one to spare.
+    fn box_value(&self, tid: usize, value: T) -> ValuePtr {
+        let version = self.stamp(tid);
+
+        if let Some(reclaim) = &self.reclaim {
+            if let Ok(mut pool) = self.values[tid].try_lock() {
+                if let Some((boxed, full)) = pool.take(reclaim) {
+                    unsafe {
+                        if full {
+                            boxed.clear::<T>();
+                        }
+                        boxed.fill(value, version);
+                    }
+                    return boxed;
+                }
+            }
+        }
 
-            return true;
+        ValuePtr::new(value, version)
+    }
+
+    // Moves out a value a pop took, see ValuePtr::claim, and retires its
+    // box, into the pool of `tid` on a fixed vector. Only the thread whose
+    // pop took `value` may call this.
+    unsafe fn take_value(&self, tid: usize, value: ValuePtr, guard: &Guard) -> T {
+        let taken = value.claim::<T>();
+
+        if let Some(reclaim) = &self.reclaim {
+            if let Ok(mut pool) = self.values[tid].try_lock() {
+                if pool.retire(value, reclaim.retire_epoch()).is_ok() {
+                    return taken;
+                }
+            }
+        }
+
+        value.retire::<T>(guard);
+        taken
+    }
+
+    // Hands back the value in a box that never made it into the vector and
+    // that nobody else can get at, and frees the box or keeps it for later.
+    unsafe fn unbox_value(&self, tid: usize, value: ValuePtr) -> T {
+        let original = value.take::<T>();
+
+        let kept = self.reclaim.is_some() && match self.values[tid].try_lock() {
+            Ok(mut pool) => pool.give_back(value).is_ok(),
+            Err(_) => false,
+        };
+        if !kept {
+            value.dealloc::<T>();
         }
 
-        let spot2: Spot = self.get_spot(newdescr.pos - 1, guard);
-        let current: Shared<usize> = spot2.load(SeqCst, guard);
+        original
+    }
+
+    // A version for a value about to be boxed. Thread `tid` hands out every
+    // num_threads-th version starting at `tid`, so versions never repeat,
+    // not even for threads that share a tid.
+    fn stamp(&self, tid: usize) -> u64 {
+        let count = self.stamps[tid].fetch_add(1, Relaxed);
+        count.wrapping_mul(self.num_threads as u64).wrapping_add(tid as u64)
+    }
 
-        let mut failures: usize = 0;
+    pub fn complete_push(&self, _spot: Spot, old: MarkedPtr<usize>, descr: &Arc<PushDescr>, guard: &Guard) -> bool {
+        let mut rawstate = loadstate(descr);
+        let mut failures = 0;
 
+        // A push at 0 has nothing below it to wait for. Anywhere else it
+        // passes if the spot below holds a value and fails if it is empty.
+        // A descriptor there gets helped, but only LIMIT times: after that
+        // the push fails rather than wait any longer. Failing is always safe,
+        // the push just tries again a spot lower, where passing would take
+        // for granted a value that may never arrive.
         while rawstate == STATE_UNDECIDED {
-            let temp = loadstate(&newdescr, guard);
-            mystate = temp.0;
-            rawstate = temp.1;
-
-            let spot2: Spot = self.get_spot(newdescr.pos - 1, guard);
-            let current: Shared<usize> = spot2.load(SeqCst, guard);
-            let unpackres = unpack_descr(current, guard);
-            match unpackres {
-                None => break,
-                _ => (),
+            let decided = if descr.pos == 0 {
+                STATE_PASSED
+            } else {
+                let (below, word) = self.load_at(descr.pos - 1, guard);
+                match unsafe { SlotWord::decode(word) } {
+                    SlotWord::Descr(base) if failures < LIMIT && !is_spent_pop(word, guard) => {
+                        failures += 1;
+                        self.complete_base(below, word, base, guard);
+                        rawstate = loadstate(descr);
+                        continue;
+                    },
+                    SlotWord::Value(_) => STATE_PASSED,
+                    _ => STATE_FAILED,
+                }
+            };
+
+            let _ = descr.state.compare_exchange(STATE_UNDECIDED, decided, AcqRel, Acquire);
+            rawstate = loadstate(descr);
+        }
+
+        // Helpers may place several descriptors for one announced push, of
+        // those that pass only the first to record itself gets to push.
+        let pushed = rawstate == STATE_PASSED && match &descr.owner {
+            None => true,
+            Some(op) => {
+                let claim = PushResult { pos: Some(descr.pos), by: Arc::as_ptr(descr).addr() };
+                record(&op.result, claim, guard);
+                unsafe { op.result.load(Acquire, guard).deref() }.by == Arc::as_ptr(descr).addr()
+            },
+        };
+
+        let new = if pushed {
+            self.size.update(descr.pos, descr.pos + 1, || self.load_at(descr.pos, guard).1 == old);
+            descr.value.word()
+        }
+        else {
+            MarkedPtr::null().with_mark(TagNotValue)
+        };
+
+        if self.replace(descr.pos, old, new, guard) {
+            unsafe { self.retire_descr(old, guard) };
+        }
+
+        pushed
+    }
+
+    // Swaps `old` at `position` for `new` unless somebody else already took
+    // `old` out, and tells whether it was us. The spot is looked up again
+    // every time, so a resize that froze it in the meantime does not make us
+    // miss the newest generation.
+    fn replace(&self, position: usize, old: MarkedPtr<usize>, new: MarkedPtr<usize>, guard: &Guard) -> bool {
+        loop {
+            let (spot, current) = self.load_at(position, guard);
+            if current != old {
+                return false;
             }
-            let baseptr = unpackres.unwrap();
-            let basedescr = unsafe { baseptr.deref() }.clone();
 
-            failures += 1;
-            if failures >= LIMIT {
-                let set_to_passed = descr.state.compare_and_set(mystate, Owned::new(STATE_PASSED), SeqCst, guard);
-                if set_to_passed.is_err() {
-                    // dbg!("Could not update the descriptor state to PASSED");
-                }
+            if spot.compare_exchange(old, new, AcqRel, Acquire, guard).is_ok() {
+                return true;
             }
+        }
+    }
 
-            self.complete_base(spot2, current, &basedescr, guard);
+    // Puts either the new value or the old one back in place of `descr`,
+    // depending on whether it is the WriteDescr its op records as writing.
+    // Whoever takes the descriptor out retires the value that lost.
+    fn complete_write(&self, old: MarkedPtr<usize>, descr: &Arc<WriteDescr>, guard: &Guard) -> bool {
+        let op = &descr.owner;
+        let claim = WriteResult { by: Arc::as_ptr(descr).addr(), found: Some(descr.old) };
+        record(&op.result, claim, guard);
+        let wrote = unsafe { op.result.load(Acquire, guard).deref() }.by == Arc::as_ptr(descr).addr();
+
+        let (kept, lost) = if wrote { (descr.new, descr.old) } else { (descr.old, descr.new) };
+        if self.replace(op.pos, old, kept.word(), guard) {
+            unsafe {
+                self.retire_descr(old, guard);
+                lost.retire::<T>(guard);
+            }
         }
 
-        let (mystate, rawstate) = loadstate(&newdescr, guard);
+        wrote
+    }
 
-        // Descriptor moved out of the way, but we still have to finish this push
-        if rawstate == STATE_UNDECIDED {
-            if current.tag() == TagNotValue {
-                let set_to_failed = descr.state.compare_and_set(mystate, Owned::new(STATE_FAILED), SeqCst, guard);
-                if set_to_failed.is_err() {
-                    // dbg!("Could not update the descriptor state to FAILED");
+    // Takes over whatever positions of `multi` nobody holds yet, decides it
+    // and hands every position back. With a `budget` it gives up after that
+    // many attempts at taking a position, still undecided, and says so.
+    //
+    // Only descriptors that settle their own spot get helped on the way. An
+    // undecided push and a pop first settle the spot below, which may be
+    // ours, so the cas_multi fails on them instead. Their spot is empty as
+    // far as readers are concerned. A push that is decided only has its
+    // own spot left to settle, and one that passed is already counted.
+    fn complete_multi(&self, multi: &Arc<MultiDescr>, budget: Option<usize>, guard: &Guard) -> bool {
+        let mut attempts = 0;
+
+        'entries: for (index, entry) in multi.entries.iter().enumerate() {
+            while entry.installed.load(Acquire) == 0 {
+                if multi.state.load(Acquire) != STATE_UNDECIDED {
+                    break 'entries;
                 }
-            }
-            else {
-                let set_to_passed = descr.state.compare_and_set(mystate, Owned::new(STATE_PASSED), SeqCst, guard);
-                if set_to_passed.is_err() {
-                    // dbg!("Could not update the descriptor state to PASSED");
+                if budget.is_some_and(|budget| attempts > budget) {
+                    return false;
+                }
+                attempts += 1;
+
+                let (spot, word) = self.load_at(entry.pos, guard);
+                match unsafe { SlotWord::decode(word) } {
+                    SlotWord::Descr(BaseDescr::MultiDescrType(slot)) if Arc::ptr_eq(&slot.parent, multi) => {
+                        let _ = entry.installed.compare_exchange(0, Arc::as_ptr(slot).addr(), AcqRel, Acquire);
+                    },
+                    SlotWord::Descr(descr @ (BaseDescr::MultiDescrType(_) | BaseDescr::PopSubDescrType(_) | BaseDescr::WriteDescrType(_))) => {
+                        self.complete_base(spot, word, descr, guard);
+                    },
+                    SlotWord::Descr(descr @ BaseDescr::PushDescrType(push)) if loadstate(push) != STATE_UNDECIDED => {
+                        self.complete_base(spot, word, descr, guard);
+                    },
+                    SlotWord::Value(found) if (multi.same)(entry.old, found) => {
+                        let slot = Arc::new(MultiSlotDescr { parent: multi.clone(), index, found });
+                        let slotptr = pack_descr(BaseDescr::MultiDescrType(slot.clone()), guard);
+
+                        // A slot that gets in but not recorded came in after
+                        // the decision and is handed back below.
+                        if spot.compare_exchange(word, slotptr, AcqRel, Acquire, guard).is_ok() {
+                            let _ = entry.installed.compare_exchange(0, Arc::as_ptr(&slot).addr(), AcqRel, Acquire);
+                        }
+                        else {
+                            unsafe { drop_descr(slotptr, guard) };
+                        }
+                    },
+                    _ => {
+                        let _ = multi.state.compare_exchange(STATE_UNDECIDED, STATE_FAILED, AcqRel, Acquire);
+                    },
                 }
             }
         }
 
-        let (_, rawstate) = loadstate(&descr, guard);
+        // Still undecided, so every position is held.
+        let _ = multi.state.compare_exchange(STATE_UNDECIDED, STATE_PASSED, AcqRel, Acquire);
 
-        if rawstate == STATE_PASSED {
-            spot.compare_and_set(old, Owned::new(newdescr.value), SeqCst, guard);
-        }
-        else {
-            spot.compare_and_set(old, Owned::new(0).with_tag(TagNotValue), SeqCst, guard);
+        for entry in &multi.entries {
+            let (_, word) = self.load_at(entry.pos, guard);
+            if let SlotWord::Descr(BaseDescr::MultiDescrType(slot)) = unsafe { SlotWord::decode(word) } {
+                if Arc::ptr_eq(&slot.parent, multi) {
+                    self.release_slot(word, slot, guard);
+                }
+            }
         }
 
-        let (_, rawstate) = loadstate(&descr, guard);
+        true
+    }
 
-        return rawstate == STATE_PASSED;
+    fn complete_multi_slot(&self, old: MarkedPtr<usize>, slot: &Arc<MultiSlotDescr>, guard: &Guard) -> bool {
+        self.complete_multi(&slot.parent, None, guard);
+        self.release_slot(old, slot, guard)
     }
 
-    pub fn pop_back(&self, tid: usize) -> Option<usize> {
+    // Puts the value `slot` stands for back in its place. Whoever takes the
+    // slot out retires the value it replaced if that one lost.
+    fn release_slot(&self, old: MarkedPtr<usize>, slot: &MultiSlotDescr, guard: &Guard) -> bool {
+        let wrote = slot.writes();
+
+        if self.replace(slot.entry().pos, old, slot.value().word(), guard) {
+            unsafe {
+                self.retire_descr(old, guard);
+                if wrote {
+                    slot.found.retire::<T>(guard);
+                }
+            }
+        }
+
+        wrote
+    }
+
+    // As in the paper, a pop places a PopDescr on the first empty spot, which
+    // is `size` unless something is in flight, and takes the value right
+    // below it at `size - 1`. If that spot turns out to be empty as well the
+    // pop moves one spot down, and once it gets to 0 the vector is empty.
+    pub fn pop_back(&self, tid: usize) -> Option<T> {
         self.help_if_needed(tid);
 
-        let guard = &epoch::pin();
-        
-        // TODO: announcement table
-    
-        let shsize = self.size.load(SeqCst, guard);
-        let sizeusizeptr = unsafe { shsize.deref() }.clone();
-        let mut pos = sizeusizeptr.load(SeqCst);
+        let guard = &self.pin(tid);
+
+        let mut pos = self.size.load();
     
-        for failures in 0..=LIMIT {
+        for _failures in 0..=LIMIT {
             if pos == 0 {
                 return None;
             }
     
-            let spot = self.get_spot(pos, guard);
-            let expectedptr = spot.load(SeqCst, guard);
-            if expectedptr.tag() == TagNotValue {
-                
-                let descr = BaseDescr::PopDescrType(Rc::new(PopDescr::new(pos)));
-                let cdescr = descr.clone();
-                let descrptr = pack_descr(descr, guard);
-    
-                match spot.compare_and_set(expectedptr, descrptr, SeqCst, guard) {
-                    Ok(new_descriptor) => {
-                        let res = self.complete_base(spot, descrptr, &cdescr, guard);
-                        if res {
-                            if let BaseDescr::PopDescrType(p) = cdescr {
-                                let child = unsafe { p.child.load(SeqCst, guard).deref() };
-    
-                                sizeusizeptr.fetch_sub(1, SeqCst);
-                                return Some(child.value);
-                            }
-                            return None
-                        }
-                        else {
-                            pos -= 1;
-                        }
-                    },
-                    Err(_) => (),
-    
+            let (spot, expectedptr) = self.load_at(pos, guard);
+            if let SlotWord::NotValue = unsafe { SlotWord::decode(expectedptr) } {
+                let descr = self.pop_descr(tid, pos);
+                let descrptr = pack_shared(descr.clone());
+
+                if spot.compare_exchange(expectedptr, descrptr, AcqRel, Acquire, guard).is_ok() {
+                    if self.complete_base(spot, descrptr, &descr, guard) {
+                        let child = match &*descr {
+                            BaseDescr::PopDescrType(pop_descr) => pop_descr.child(guard),
+                            _ => unreachable!(),
+                        };
+                        return child.and_then(|child| child.value).map(|value| unsafe { self.take_value(tid, value, guard) });
+                    }
+
+                    pos -= 1;
+                }
+                else {
+                    unsafe { drop_descr(descrptr, guard) };
                 }
             }
             else {
-                match unpack_descr(expectedptr, guard) {
-                    Some(x) => {
-                        let descr = unsafe { x.deref() }.clone();
-                        self.complete_base(spot, expectedptr, &descr, guard);
+                match unsafe { SlotWord::decode(expectedptr) } {
+                    SlotWord::Descr(descr) => {
+                        self.complete_base(spot, expectedptr, descr, guard);
                     }
-                    None => {
+                    _ => {
                         pos += 1;
                     }
                 }
             }
-            
-            // announcement table stuff
         }
 
         let pop_op = Arc::new(PopOp::new());
         let base_op = BaseOp::PopOpType(pop_op.clone());
-        self.announce_op(tid, Owned::new(base_op).into_shared(guard), guard);
+        self.announce_op(tid, MarkedPtr::from_box(Box::new(base_op)), guard);
 
-        unsafe { pop_op.result.load(SeqCst, guard).deref() }.clone()
+        unsafe { pop_op.result.load(Acquire, guard).deref() }.value.map(|value| unsafe { self.take_value(tid, value, guard) })
     }
 
-    pub fn complete_pop(&self, spot: Spot, old: Shared<usize>, pop_descriptor: Rc<PopDescr>, guard: &Guard) -> bool {
+    // The counterpart of try_push_back for code written against a bounded
+    // vector. A pop never runs out of room, so it only fails on an empty
+    // vector, just like pop_back.
+    pub fn try_pop_back(&self, tid: usize) -> Option<T> {
+        self.pop_back(tid)
+    }
 
-        let previous_spot = self.get_spot(pop_descriptor.pos - 1, guard);
+    pub fn complete_pop(&self, _spot: Spot, old: MarkedPtr<usize>, pop_descriptor: Arc<PopDescr>, guard: &Guard) -> bool {
         let mut failures = 0;
-        
-        loop {
-            if !pop_descriptor.child.load(SeqCst, guard).is_null() {
-                break
-            }
 
-            if failures >= LIMIT {
-                let failed_child = PopSubDescr::with_state_and_parent(STATE_FAILED, pop_descriptor.clone());
-                let failed_child_owned = Owned::new(failed_child);
-                pop_descriptor.child.compare_and_set(Shared::null(), failed_child_owned, SeqCst, guard);
+        while pop_descriptor.child(guard).is_none() {
+            let (previous_spot, expected) = self.load_at(pop_descriptor.pos - 1, guard);
 
+            // Nothing left below to take. An announced pop never gives up
+            // for any other reason, whether it wins is decided by its op.
+            let below = unsafe { SlotWord::decode(expected) };
+            let empty_below = matches!(below, SlotWord::NotValue) || is_spent_pop(expected, guard);
+            if empty_below || (failures >= LIMIT && pop_descriptor.owner.is_none()) {
+                let failed_child = Arc::new(PopSubDescr::with_state_and_parent(STATE_FAILED, &pop_descriptor));
+                pop_descriptor.settle_on(&failed_child, guard);
                 break
             }
 
             failures += 1;
 
-            let expected = previous_spot.load(SeqCst, guard);
-            if expected.tag() == TagNotValue {
-                let failed_child = PopSubDescr::with_state_and_parent(STATE_FAILED, pop_descriptor.clone());
-                let failed_child_owned = Owned::new(failed_child);
-                pop_descriptor.child.compare_and_set(Shared::null(), failed_child_owned, SeqCst, guard);
-            }
-            
-            match unpack_descr(expected, guard) {
-                Some(descriptor) => {
-                    let descr = unsafe { descriptor.deref() };
-                    self.complete_base(previous_spot.clone(), expected, descr, guard);
+            match below {
+                SlotWord::Descr(descr) => {
+                    self.complete_base(previous_spot, expected, descr, guard);
                 },
-                None => {
-                    let raw_value = unsafe { expected.deref() }.clone();
-                    let raw_sub = PopSubDescr::new(pop_descriptor.clone(), raw_value);
-                    
-                    let pop_sub_desc = Rc::new(raw_sub);
-                    let base_descr = BaseDescr::PopSubDescrType(pop_sub_desc);
-                    let packed = pack_descr(base_descr, guard);
-
-                    let previous_spot_replacement = previous_spot.compare_and_set(expected, packed, SeqCst, guard);
-
-                    if previous_spot_replacement.is_ok() {
-                        let child_replacement = pop_descriptor.child.compare_and_set(Shared::null(), Owned::new(PopSubDescr::new(pop_descriptor.clone(), raw_value)), SeqCst, guard);
-                        if child_replacement.is_ok() {
-                            previous_spot.compare_and_set(packed, Shared::null().with_tag(TagNotValue), SeqCst, guard);
-                        }
-                        else {
-                            let packed_2 = pack_descr(BaseDescr::PopDescrType(pop_descriptor.clone()), guard);
-                            previous_spot.compare_and_set(packed_2, expected, SeqCst, guard);
+                SlotWord::Value(value) => {
+                    let (claim, pooled) = match pop_descriptor.take_claim(value) {
+                        Some(claim) => (claim, true),
+                        None => (Arc::new(BaseDescr::PopSubDescrType(Arc::new(PopSubDescr::new(&pop_descriptor, value)))), false),
+                    };
+                    let pop_sub_desc = match &*claim {
+                        BaseDescr::PopSubDescrType(sub) => sub.clone(),
+                        _ => unreachable!(),
+                    };
+                    let packed = pack_shared(claim);
+
+                    if previous_spot.compare_exchange(expected, packed, AcqRel, Acquire, guard).is_ok() {
+                        self.complete_pop_sub(previous_spot, packed, pop_sub_desc, guard);
+                    }
+                    else {
+                        unsafe { drop_descr(packed, guard) };
+
+                        // Nobody saw the claim, the next attempt can have it.
+                        // Whoever takes it writes to it, so our own reference
+                        // has to be gone before we hand it back.
+                        drop(pop_sub_desc);
+                        if pooled {
+                            pop_descriptor.return_claim();
                         }
                     }
                 },
+                // Empty spots were dealt with above and load_at never
+                // hands out one that is still waiting to be copied.
+                SlotWord::NotValue | SlotWord::NotCopied => unreachable!(),
             }
         }
 
-        let packed_pop_descriptor = pack_descr(BaseDescr::PopDescrType(pop_descriptor.clone()), guard);
+        let child = pop_descriptor.child(guard).expect("a pop that stopped looking has a child");
+        let took = self.pop_took(&pop_descriptor, child, guard);
 
-        spot.compare_and_set(packed_pop_descriptor, Shared::null().with_tag(TagNotValue), SeqCst, guard);
+        if child.state != STATE_FAILED {
+            self.finish_pop(&pop_descriptor, child, took, guard);
+        }
 
-        let child_state = unsafe { pop_descriptor.child.load(SeqCst, guard).deref() }.state;
+        if self.replace(pop_descriptor.pos, old, MarkedPtr::null().with_mark(TagNotValue), guard) {
+            unsafe { self.retire_descr(old, guard) };
+        }
 
-        return child_state != STATE_FAILED;
+        took
     }
-        
 
-    pub fn complete_pop_sub(&self, spot: Spot, old: Shared<usize>, descr: Rc<PopSubDescr>, guard: &Guard) -> bool {
-        let f = descr.as_ref();
-        let owned_descr = Owned::new(f.clone()).into_shared(guard);
-        let cas_result = descr.parent.child
-            .compare_and_set(Shared::null(), owned_descr, SeqCst, guard);
-    
-        if let Err(e) = cas_result {
-            // println!("The inserting the pop sub desc failed {:?}", e);
+    pub fn complete_pop_sub(&self, _spot: Spot, old: MarkedPtr<usize>, descr: Arc<PopSubDescr>, guard: &Guard) -> bool {
+        // A pop is only freed once the child it settled on is out of its
+        // spot, so a claim that outlived its pop was not the child either.
+        let parent = descr.parent.upgrade();
+        if let Some(parent) = &parent {
+            parent.settle_on(&descr, guard);
+
+            let child = parent.child(guard).expect("a pop that was just settled has a child");
+            if ptr::eq(child, &*descr) {
+                let took = self.pop_took(parent, child, guard);
+                self.finish_pop(parent, child, took, guard);
+
+                return took;
+            }
         }
-    
-        let result = if descr.parent.child.load(SeqCst, guard) == owned_descr {
-            spot.compare_and_set(old, Owned::new(0).with_tag(TagNotValue), SeqCst, guard)
-        } else {
-            spot.compare_and_set(old, Owned::new(descr.value), SeqCst, guard)
+
+        // Any other child means either the pop gave up or this claim came in
+        // after the pop had already taken a value, so ours goes back.
+        if let Some(value) = descr.value {
+            if self.replace(descr.pos, old, value.word(), guard) {
+                unsafe { self.retire_descr(old, guard) };
+            }
+        }
+
+        false
+    }
+
+    // Whether `pop` keeps the value its child claimed. Several pops of one
+    // announced op may each claim a value, but only the first to record
+    // itself in the op keeps it, the others put theirs back.
+    fn pop_took(&self, pop: &Arc<PopDescr>, child: &PopSubDescr, guard: &Guard) -> bool {
+        if child.state == STATE_FAILED {
+            return false;
+        }
+
+        match &pop.owner {
+            None => true,
+            Some(op) => {
+                let claim = PopResult { value: child.value, by: Arc::as_ptr(pop).addr() };
+                record(&op.result, claim, guard);
+                unsafe { op.result.load(Acquire, guard).deref() }.by == Arc::as_ptr(pop).addr()
+            },
+        }
+    }
+
+    // Releases the spot below `pop` that its child claimed: a pop that took
+    // the value is counted and leaves the spot empty, any other puts it back.
+    fn finish_pop(&self, pop: &Arc<PopDescr>, child: &PopSubDescr, took: bool, guard: &Guard) {
+        let below = pop.pos - 1;
+        let claimed = |word: MarkedPtr<usize>| match unsafe { SlotWord::decode(word) } {
+            SlotWord::Descr(BaseDescr::PopSubDescrType(sub)) => ptr::eq(&**sub, child),
+            _ => false,
         };
-    
-        if let Err(e) = result {
-            // println!("Something went wrong {:?}", e)
+
+        let released = match child.value {
+            Some(value) if !took => value.word(),
+            _ => {
+                self.size.update(pop.pos, below, || claimed(self.load_at(below, guard).1));
+                MarkedPtr::null().with_mark(TagNotValue)
+            },
+        };
+
+        loop {
+            let (spot, current) = self.load_at(below, guard);
+            if !claimed(current) {
+                return;
+            }
+
+            if spot.compare_exchange(current, released, AcqRel, Acquire, guard).is_ok() {
+                unsafe { self.retire_descr(current, guard) };
+                return;
+            }
         }
-    
-        descr.parent.child.load(SeqCst, guard) == owned_descr
+    }
+
+}
+
+impl<T: Clone + Send + Sync + 'static> WaitFreeVector<T> {
+    // Descriptors found at `pos` are helped to completion first, so a push
+    // is only seen once it has PASSED and a pop claiming the spot has either
+    // taken the value or put it back. A value popped while we clone it is
+    // read as gone, which it was for a moment after we found it.
+    pub fn at(&self, tid: usize, pos: usize) -> Option<T> {
+        let guard = &self.pin(tid);
+        self.read(pos, guard).and_then(|value| unsafe { value.peek(T::clone) })
+    }
+
+    // Like at(), together with the version of the value, for a later
+    // cwrite_versioned. Every value that goes into a spot, through a push
+    // or a cwrite, gets a version no other value of the vector ever had. A
+    // resize moves the value, version and all, and a pop takes both away.
+    pub fn at_versioned(&self, tid: usize, pos: usize) -> Option<(T, u64)> {
+        let guard = &self.pin(tid);
+        self.read(pos, guard).and_then(|value| unsafe { value.peek(|found: &T| (found.clone(), value.version())) })
+    }
+
+    // Returns the contents of the vector as they were at a single point in
+    // time. Two collects that saw exactly the same words mean nothing changed
+    // in between, and since we stay pinned no word can be freed and reused to
+    // fake that. This retries while writers keep changing the vector, so it
+    // is lock-free rather than wait-free.
+    pub fn snapshot(&self, tid: usize) -> Vec<T> {
+        self.help_if_needed(tid);
+
+        let guard = &self.pin(tid);
+        let mut previous = self.collect(guard);
+
+        loop {
+            // Keeps two snapshots from seeing two writes in opposite orders.
+            fence(SeqCst);
+            let current = self.collect(guard);
+
+            // A value popped since is no longer ours to clone, and the
+            // vector changed, so that is another round as well.
+            if current == previous {
+                let values = current.iter().map(|value| unsafe { value.peek(T::clone) }).collect();
+                if let Some(values) = values {
+                    return values;
+                }
+            }
+            previous = current;
+        }
+    }
+
+    // Reads `range` into `out`, which has to be just as long, under a single
+    // pin. Like at() every position is settled first, so a value only shows
+    // up once the push that wrote it is complete. Positions that turn out
+    // empty leave their element of `out` as it was. The values read need
+    // not have been in the vector at the same time, see snapshot_range.
+    pub fn read_range(&self, tid: usize, range: Range<usize>, out: &mut [T]) -> Vec<RangeSlot> {
+        assert_eq!(range.len(), out.len(), "read_range needs one output element per position");
+
+        let guard = &self.pin(tid);
+        let length = self.size.load();
+        let values = self.collect_range(range.clone(), guard)
+            .into_iter()
+            .map(|value| value.and_then(|value| unsafe { value.peek(T::clone) }))
+            .collect();
+
+        fill_range(range.start, length, values, out)
+    }
+
+    // Same as read_range, but everything reported held at one single point in
+    // time. Two collects of the range that saw the same words with the size
+    // not changing at all around them mean nothing changed in between. Like
+    // snapshot this retries while writers keep at it, so it is only lock-free.
+    pub fn snapshot_range(&self, tid: usize, range: Range<usize>, out: &mut [T]) -> Vec<RangeSlot> {
+        assert_eq!(range.len(), out.len(), "snapshot_range needs one output element per position");
+
+        self.help_if_needed(tid);
+
+        let guard = &self.pin(tid);
+        let mut before = self.size.load_versioned();
+        let mut previous = self.collect_range(range.clone(), guard);
+
+        loop {
+            fence(SeqCst);
+            let middle = self.size.load_versioned();
+            let current = self.collect_range(range.clone(), guard);
+            let after = self.size.load_versioned();
+
+            if before == after && current == previous {
+                let values: Option<Vec<Option<T>>> = current.iter()
+                    .map(|value| match value {
+                        Some(value) => unsafe { value.peek(T::clone) }.map(Some),
+                        None => Some(None),
+                    })
+                    .collect();
+
+                // See snapshot.
+                if let Some(values) = values {
+                    return fill_range(range.start, after & SIZE_MASK, values, out);
+                }
+            }
+
+            before = middle;
+            previous = current;
+        }
+    }
+
+    // Cheap iteration that does not wait for writers, see Iter for what it
+    // does and does not promise. On a fixed vector the iterator also pins
+    // the vector's own epochs as `tid` until epoch.rs runs what `guard`
+    // deferred, and for as long as that takes the pools cannot reuse anything.
+    pub fn iter<'g>(&'g self, tid: usize, guard: &'g Guard) -> Iter<'g, T> {
+        if let Some(reclaim) = &self.reclaim {
+            let (reclaim, pin) = (reclaim.clone(), reclaim.pin(tid));
+            guard.defer(move || reclaim.unpin(tid, pin));
+            guard.flush();
+        }
+
+        Iter::new(self, guard)
+    }
+
+    // Replaces the value at `pos` with `new` if it still is the one
+    // at_versioned reported `version` for. A value equal to that one but
+    // written since, say by a cwrite from A to B and back to A, has another
+    // version and makes this fail.
+    pub fn cwrite_versioned(&self, tid: usize, pos: usize, version: u64, new: T) -> bool {
+        self.help_if_needed(tid);
+        let guard = &self.pin(tid);
+
+        let matches = |current: ValuePtr| unsafe { current.version() } == version;
+        self.write(tid, pos, new, matches, || Expected::Version(version), guard).is_ok()
+    }
+
+    // What the conditional writes share. `matches` tells whether the value in
+    // the spot is the one to replace, `expected` says the same to helpers once
+    // the write has to be announced. Returns the value replaced, or the one
+    // found instead, both good for as long as `guard` is.
+    fn write(&self, tid: usize, pos: usize, new: T, matches: impl Fn(ValuePtr) -> bool, expected: impl FnOnce() -> Expected, guard: &Guard) -> Result<ValuePtr, Option<ValuePtr>> {
+        if pos >= self.size.load() {
+            return Err(None);
+        }
+
+        let newptr = ValuePtr::new(new, self.stamp(tid));
+
+        for _failures in 0..=LIMIT {
+            let (spot, oldptr) = self.load_at(pos, guard);
+            let current = match unsafe { SlotWord::decode(oldptr) } {
+                SlotWord::Descr(descr) => {
+                    self.complete_base(spot, oldptr, descr, guard);
+                    continue;
+                },
+                SlotWord::Value(current) if matches(current) => current,
+                other => {
+                    unsafe { newptr.free::<T>() };
+
+                    return Err(match other {
+                        SlotWord::Value(current) => Some(current),
+                        _ => None,
+                    });
+                },
+            };
+
+            if spot.compare_exchange(oldptr, newptr.word(), AcqRel, Acquire, guard).is_ok() {
+                unsafe { current.retire::<T>(guard) };
+                return Ok(current);
+            }
+        }
+
+        // Our box goes to the op, helpers install copies of it.
+        let op = Arc::new(WriteOp::new::<T>(pos, expected(), newptr));
+        self.announce_op(tid, MarkedPtr::from_box(Box::new(BaseOp::WriteOpType(op.clone()))), guard);
+
+        // Whatever the result points at was in the vector after we pinned,
+        // so it is still there for as long as `guard` is.
+        let result = unsafe { op.result.load(Acquire, guard).deref() };
+        match result.by {
+            0 => Err(result.found),
+            _ => Ok(result.found.expect("a write that happened replaced a value")),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq + Send + Sync + 'static> WaitFreeVector<T> {
+    pub fn cwrite(&self, tid: usize, pos: usize, old: T, new: T) -> bool {
+        self.help_if_needed(tid);
+        let guard = &self.pin(tid);
+
+        self.write_if_equal(tid, pos, old, new, guard).is_ok()
+    }
+
+    // Replaces the value at `pos` with `new` if it equals `current`, like
+    // cwrite. Hands back the value it replaced, or else the one it found
+    // instead, None if `pos` is past the end.
+    pub fn compare_exchange(&self, tid: usize, pos: usize, current: T, new: T) -> Result<T, Option<T>> {
+        self.help_if_needed(tid);
+        let guard = &self.pin(tid);
+
+        // The value we replaced is out of the vector, no pop can take it. The
+        // one found instead is still in there, and one that got popped in
+        // the meantime is gone like a position past the end.
+        let copy = |value: ValuePtr| unsafe { value.peek(T::clone) };
+        self.write_if_equal(tid, pos, current, new, guard)
+            .map(|replaced| copy(replaced).expect("nobody pops a value we replaced"))
+            .map_err(|found| found.and_then(copy))
+    }
+
+    // Replaces the value at every position of `entries` with its new value
+    // if each of them holds its expected value, all at once, or else changes
+    // nothing. `entries` are (position, expected, new), and may list a
+    // position only once.
+    pub fn cas_multi(&self, tid: usize, entries: &[(usize, T, T)]) -> bool {
+        let mut sorted: Vec<&(usize, T, T)> = entries.iter().collect();
+        sorted.sort_by_key(|(pos, _, _)| *pos);
+        assert!(sorted.windows(2).all(|pair| pair[0].0 != pair[1].0), "cas_multi got a position twice");
+
+        self.help_if_needed(tid);
+        let guard = &self.pin(tid);
+
+        let size = self.size.load();
+        if sorted.iter().any(|(pos, _, _)| *pos >= size) {
+            return false;
+        }
+
+        let entries = sorted.into_iter()
+            .map(|(pos, old, new)| (*pos, ValuePtr::new(old.clone(), 0), ValuePtr::new(new.clone(), self.stamp(tid))))
+            .collect();
+        let multi = Arc::new(MultiDescr::new::<T>(entries, same_value::<T>));
+
+        if !self.complete_multi(&multi, Some(LIMIT), guard) {
+            self.announce_op(tid, MarkedPtr::from_box(Box::new(BaseOp::MultiOpType(multi.clone()))), guard);
+        }
+
+        multi.state.load(Acquire) == STATE_PASSED
+    }
+
+    fn write_if_equal(&self, tid: usize, pos: usize, current: T, new: T, guard: &Guard) -> Result<ValuePtr, Option<ValuePtr>> {
+        let matches = |value: ValuePtr| unsafe { value.peek(|value: &T| *value == current) }.unwrap_or(false);
+        self.write(tid, pos, new, matches, || Expected::Value(ValuePtr::new(current.clone(), 0), same_value::<T>), guard)
     }
 }
 
 struct Contiguous {
     // vector: Atomic<WaitFreeVector>,
-    old: Atomic<Contiguous>,
+    old: AtomicMarkablePtr<Contiguous>,
     capacity: usize,
 
     // array is a regular array of atomic pointers
-    array: Atomic<Vec<Spot>>,
+    array: AtomicMarkablePtr<Vec<Spot>>,
+
+    // The first `prefix` spots start out as TagNotCopied and are filled from
+    // `old`. Threads claim them MIGRATION_CHUNK at a time through
+    // `next_chunk`; once `copied` reaches `prefix` the old generation is retired.
+    prefix: usize,
+    next_chunk: AtomicUsize,
+    copied: AtomicUsize,
+
+    // Set while a shrink_to_fit of this generation is in progress.
+    shrink: AtomicMarkablePtr<Shrink>,
+
+    // The generation that replaces this one, announced before it is
+    // installed so that any thread can finish the job. Set at most once.
+    next: AtomicMarkablePtr<Contiguous>,
 }
 
 impl Contiguous {
@@ -923,52 +2062,136 @@ impl Contiguous {
     pub fn new(capacity: usize) -> Contiguous {
         let mut arr = Vec::new();
 
-        for failures in 0..capacity {
-            let init: Shared<usize> = Shared::null().with_tag(TagNotValue);
+        for _failures in 0..capacity {
+            let init: MarkedPtr<usize> = MarkedPtr::null().with_mark(TagNotValue);
             arr.push(make_spot(init));
         }
 
         Contiguous {
-            old: Atomic::null(),
+            old: AtomicMarkablePtr::null(),
+            capacity,
+            array: AtomicMarkablePtr::new(arr),
+            prefix: 0,
+            next_chunk: AtomicUsize::new(0),
+            copied: AtomicUsize::new(0),
+            shrink: AtomicMarkablePtr::null(),
+            next: AtomicMarkablePtr::null(),
+        }
+    }
+
+    // A new generation of `capacity` spots whose prefix still lives in `old`.
+    fn from_old(old: MarkedPtr<Contiguous>, capacity: usize) -> Contiguous {
+        let prefix = unsafe { old.deref() }.capacity.min(capacity);
+
+        let mut arr: Vec<Spot> = Vec::with_capacity(capacity);
+        for i in 0..capacity {
+            let tag = if i < prefix { TagNotCopied } else { TagNotValue };
+            arr.push(make_spot(MarkedPtr::null().with_mark(tag)));
+        }
+
+        Contiguous {
+            old: if prefix > 0 { AtomicMarkablePtr::from(old) } else { AtomicMarkablePtr::null() },
             capacity,
-            array: Atomic::new(arr),
+            array: AtomicMarkablePtr::new(arr),
+            prefix,
+            next_chunk: AtomicUsize::new(0),
+            copied: AtomicUsize::new(0),
+            shrink: AtomicMarkablePtr::null(),
+            next: AtomicMarkablePtr::null(),
         }
     }
 
     pub fn copy_value(&self, position: usize, guard: &Guard) {
-        // Load the old Contiguous structure to copy from
-        let oldptr = self.old.load(SeqCst, guard);
-        assert!(!oldptr.is_null(), "If we're in copy_value, our pointer to the old vector must exist");
+        // Load the old Contiguous structure to copy from. It is only gone once
+        // every spot has been copied, in which case there is nothing left to do.
+        let oldptr = self.old.load(Acquire, guard);
+        if oldptr.is_null() {
+            return;
+        }
 
         // Deref and get the old vector
         let old = unsafe { oldptr.deref() };
-        let load_vec = unsafe { old.array.load(SeqCst, guard).deref() };
+        let load_vec = unsafe { old.array.load(Acquire, guard).deref() };
 
-        if position < load_vec.len() {
-            let val = load_vec[position].load(SeqCst, guard);
-            if val.tag() == TagNotCopied {
-                old.copy_value(position, guard);
-            }
+        // Freeze the old spot first. Anyone still holding it from before the
+        // resize would otherwise be able to change it after we copied it.
+        let val = load_vec[position].mark(TagResize, AcqRel, guard);
 
-            // Copying over the value from the old vector into our current vector
-            let our_vector = unsafe { self.array.load(SeqCst, guard).deref() };
-            let current_spot = our_vector[position].clone();
+        // The old generation was fully migrated before this one was installed.
+        debug_assert!(val.mark() & !TagResize != TagNotCopied);
 
-            let expected_value = Shared::<usize>::null().with_tag(TagNotCopied);
+        // Copying over the value from the old vector into our current vector
+        let our_vector = unsafe { self.array.load(Acquire, guard).deref() };
+        let expected_value = MarkedPtr::<usize>::null().with_mark(TagNotCopied);
+        let frozen_value = val.with_mark(val.mark() & !TagResize);
 
-            let reloaded_old_value = load_vec[position].load(SeqCst, guard);
-            let updated_our_vector = current_spot.compare_and_set(expected_value, reloaded_old_value, SeqCst, guard);
-            // if updated_our_vector.is_err() {
-            //     println!("Couldn't overwrite the spot in our vector");
-            // }
+        // A thread that finds `old` gone takes every spot to be copied, so
+        // each copy is released through the count and then through `old`.
+        if our_vector[position].compare_exchange(expected_value, frozen_value, AcqRel, Acquire, guard).is_ok()
+            && self.copied.fetch_add(1, AcqRel) + 1 == self.prefix {
+            self.retire_old(guard);
         }
     }
 
+    // Claims the next chunk of spots that still have to come over from the
+    // old generation and copies it. Returns false once every chunk is claimed.
+    pub fn help_migrate(&self, guard: &Guard) -> bool {
+        // Chunks only spread the work. Every spot is copied through a CAS on
+        // the spot itself, so handing out a chunk twice would do no harm.
+        if self.old.load(Acquire, guard).is_null() || self.next_chunk.load(Relaxed) >= self.prefix {
+            return false;
+        }
+
+        let start = self.next_chunk.fetch_add(MIGRATION_CHUNK, Relaxed);
+        if start >= self.prefix {
+            return false;
+        }
+
+        let end = (start + MIGRATION_CHUNK).min(self.prefix);
+        self.copy_range(start, end, guard);
+
+        true
+    }
+
+    // Copies every spot that has not been copied yet. Chunks claimed by
+    // threads that have not finished them are copied here as well, so this
+    // never waits on anybody.
+    pub fn migrate(&self, guard: &Guard) {
+        while self.help_migrate(guard) {}
+
+        if !self.old.load(Acquire, guard).is_null() {
+            self.copy_range(0, self.prefix, guard);
+        }
+    }
+
+    fn copy_range(&self, start: usize, end: usize, guard: &Guard) {
+        let vec = unsafe { self.array.load(Acquire, guard).deref() };
+        for (position, spot) in vec.iter().enumerate().take(end).skip(start) {
+            if spot.load(Acquire, guard).mark() == TagNotCopied {
+                self.copy_value(position, guard);
+            }
+        }
+    }
+
+    // Unlinks the old generation once all of it lives in this one. The
+    // values themselves were moved, only the old spots are freed.
+    fn retire_old(&self, guard: &Guard) {
+        let old = self.old.swap(MarkedPtr::null(), AcqRel, guard);
+        if !old.is_null() {
+            Contiguous::retire(old, guard);
+        }
+    }
+
+    // Frees a generation that is no longer reachable from the vector.
+    fn retire(old: MarkedPtr<Contiguous>, guard: &Guard) {
+        unsafe { old.defer_destroy(guard) };
+    }
+
     pub fn get_spot(&self, position: usize, guard: &Guard) -> Spot {
-        let vec = unsafe {self.array.load(SeqCst,guard).deref()};
-        let spot = vec[position].load(SeqCst, guard);
+        let vec = unsafe {self.array.load(Acquire,guard).deref()};
+        let spot = vec[position].load(Acquire, guard);
 
-        if spot.tag() == TagNotCopied {
+        if spot.mark() == TagNotCopied {
             self.copy_value(position, guard);
         }
 
@@ -976,48 +2199,319 @@ impl Contiguous {
     }
 }
 
+// A generation owns its spots and its pending shrink but not `old`, which is
+// either retired on its own once migrated or freed by the vector's Drop, nor
+// `next`, which by the time a generation is retired is the installed one.
+impl Drop for Contiguous {
+    fn drop(&mut self) {
+        let guard = unsafe { epoch::unprotected() };
+
+        drop(unsafe { self.array.load(Relaxed, guard).into_box() });
+
+        let shrink = self.shrink.load(Relaxed, guard);
+        if !shrink.is_null() {
+            drop(unsafe { shrink.into_box() });
+        }
+    }
+}
+
+// A shrink_to_fit in progress on one generation. `state` goes from
+// STATE_UNDECIDED to STATE_PASSED once every spot from `target` on is sealed,
+// or to STATE_FAILED if one of them already held something.
+struct Shrink {
+    target: usize,
+    state: AtomicU8,
+}
+
+impl Shrink {
+    fn new(target: usize) -> Shrink {
+        Shrink {
+            target,
+            state: AtomicU8::new(STATE_UNDECIDED),
+        }
+    }
+
+    // A sealed spot is a frozen TagNotValue pointing at the shrink that sealed
+    // it, so seals of an abandoned shrink are never mistaken for a newer one's.
+    fn seal_word<'g>(&self, this: MarkedPtr<'g, Shrink>) -> MarkedPtr<'g, usize> {
+        this.cast::<usize>().with_mark(TagNotValue | TagResize)
+    }
+}
+
 // PopDescr consists solely of a reference to a PopSubDescr (child) which is initially Null.
-#[derive(Clone)]
+// The child is the very PopSubDescr that took the value, so a helper that
+// claims a spot for a pop that is already decided can tell it came too late.
 pub struct PopDescr {
     pos: usize,
-    child: Atomic<PopSubDescr>,
-    owner: Atomic<BaseOp>,
+    // Holds a reference to the child, taken with Arc::into_raw.
+    child: AtomicMarkablePtr<PopSubDescr>,
+    owner: Option<Arc<PopOp>>,
+    // A pop out of a DescrPool comes with the claim it places below itself
+    // the first time, so claiming a value does not allocate either. Whoever
+    // sets `claim_taken` gets to use it.
+    claim: Option<Arc<BaseDescr>>,
+    claim_taken: AtomicBool,
+    // Some for a descriptor the pool of a fixed vector keeps, see DescrPool.
+    retired: Option<AtomicUsize>,
 }
 
 impl PopDescr {
     pub fn new(pos: usize) -> PopDescr {
+        PopDescr::with_parts(pos, None, None)
+    }
+
+    // A descriptor placed by whoever helps the announced pop `owner`.
+    pub fn with_owner(pos: usize, owner: Arc<PopOp>) -> PopDescr {
+        PopDescr::with_parts(pos, Some(owner), None)
+    }
+
+    fn with_parts(pos: usize, owner: Option<Arc<PopOp>>, claim: Option<Arc<BaseDescr>>) -> PopDescr {
         PopDescr {
-            pos: pos,
-            child: Atomic::null(),
-            owner: Atomic::null(),
+            pos,
+            child: AtomicMarkablePtr::null(),
+            owner,
+            claim,
+            claim_taken: AtomicBool::new(false),
+            retired: None,
+        }
+    }
+
+    // A pop together with its claim, which points back at it for good.
+    // Both get a stamp if the pool keeps them.
+    pub(crate) fn pooled(pos: usize, kept: bool) -> Arc<PopDescr> {
+        let stamp = || kept.then(|| AtomicUsize::new(0));
+
+        Arc::new_cyclic(|parent| {
+            let claim = PopSubDescr {
+                parent: parent.clone(),
+                pos: pos.wrapping_sub(1),
+                value: None,
+                state: STATE_UNDECIDED,
+                retired: stamp(),
+            };
+
+            let claim = Some(Arc::new(BaseDescr::PopSubDescrType(Arc::new(claim))));
+            let mut pop = PopDescr::with_parts(pos, None, claim);
+            pop.retired = stamp();
+            pop
+        })
+    }
+
+    pub(crate) fn child<'g>(&self, guard: &'g Guard) -> Option<&'g PopSubDescr> {
+        let child = self.child.load(Acquire, guard);
+        (!child.is_null()).then(|| unsafe { child.deref() })
+    }
+
+    // Makes `child` the child unless the pop already has one.
+    fn settle_on(&self, child: &Arc<PopSubDescr>, guard: &Guard) {
+        let raw = MarkedPtr::from_raw(Arc::into_raw(child.clone()));
+        if self.child.compare_exchange(MarkedPtr::null(), raw, AcqRel, Acquire, guard).is_err() {
+            drop(unsafe { Arc::from_raw(raw.as_raw()) });
+        }
+    }
+
+    // The claim that came with the pop, set up to take `value`, unless it is
+    // taken already or the pop has none.
+    fn take_claim(&self, value: ValuePtr) -> Option<Arc<BaseDescr>> {
+        let claim = self.claim.as_ref()?;
+        if self.claim_taken.swap(true, Acquire) {
+            return None;
+        }
+
+        if let BaseDescr::PopSubDescrType(sub) = &**claim {
+            // Until it is in a spot nobody but us looks at the claim.
+            unsafe { pool::exclusive(sub) }.value = Some(value);
+        }
+
+        Some(claim.clone())
+    }
+
+    // Hands the claim back after it failed to get into its spot.
+    fn return_claim(&self) {
+        self.claim_taken.store(false, Release);
+    }
+}
+
+impl Drop for PopDescr {
+    fn drop(&mut self) {
+        let child = self.child.load(Relaxed, unsafe { epoch::unprotected() });
+        if !child.is_null() {
+            drop(unsafe { Arc::from_raw(child.as_raw()) });
         }
     }
 }
 
 // PopSubDescr consists of a reference to a previously placed PopDescr (parent)
-// and the value that was replaced by the PopSubDescr (value).
-#[derive(Clone)]
+// and the value that was replaced by the PopSubDescr (value), which sat at `pos`.
+// The parent is weak since the parent's child points back at us.
 // #[derive(Debug)]
 pub struct PopSubDescr {
-    parent: Rc<PopDescr>,
-    value: usize,
-    state: u8
+    parent: Weak<PopDescr>,
+    pos: usize,
+    // None only for the FAILED child of a pop that found nothing to take.
+    value: Option<ValuePtr>,
+    state: u8,
+    // Some for the claim of a pop the pool of a fixed vector keeps.
+    retired: Option<AtomicUsize>,
 }
 
 impl PopSubDescr {
-    pub fn new(parent: Rc<PopDescr>, value: usize) -> PopSubDescr {
+    pub(crate) fn new(parent: &Arc<PopDescr>, value: ValuePtr) -> PopSubDescr {
         PopSubDescr {
-            parent: parent,
-            value: value,
-            state: STATE_UNDECIDED
+            parent: Arc::downgrade(parent),
+            pos: parent.pos - 1,
+            value: Some(value),
+            state: STATE_UNDECIDED,
+            retired: None,
         }
     }
 
-    pub fn with_state_and_parent(state: u8, parent: Rc<PopDescr>) -> PopSubDescr {
+    pub fn with_state_and_parent(state: u8, parent: &Arc<PopDescr>) -> PopSubDescr {
         PopSubDescr {
-            parent: parent,
-            value: 0,
-            state: state
+            parent: Arc::downgrade(parent),
+            pos: parent.pos - 1,
+            value: None,
+            state,
+            retired: None,
+        }
+    }
+}
+
+impl<T> Drop for WaitFreeVector<T> {
+    fn drop(&mut self) {
+        // Nobody else can get at the vector anymore, so everything can go
+        // right away instead of being deferred.
+        let guard = unsafe { epoch::unprotected() };
+
+        match &self.storage {
+            Storage::Contiguous(storage) => {
+                let current = storage.load(Relaxed, guard);
+                let contig = unsafe { current.deref() };
+                let vec = unsafe { contig.array.load(Relaxed, guard).deref() };
+
+                // A spot still TagNotCopied has its word in an older
+                // generation. Every other word in there is a frozen copy of
+                // one we free through the newer generation.
+                for (position, spot) in vec.iter().enumerate() {
+                    let mut word = spot.load(Relaxed, guard);
+                    let mut generation = contig;
+
+                    while word.mark() == TagNotCopied {
+                        generation = unsafe { generation.old.load(Relaxed, guard).deref() };
+                        let old = unsafe { generation.array.load(Relaxed, guard).deref() };
+                        word = old[position].load(Relaxed, guard);
+                        word = word.with_mark(word.mark() & !TagResize);
+                    }
+
+                    unsafe { drop_word::<T>(word, guard) };
+                }
+
+                // A resize announced but never installed.
+                let next = contig.next.load(Relaxed, guard);
+                if !next.is_null() {
+                    drop(unsafe { next.into_box() });
+                }
+
+                let mut generation = current;
+                while !generation.is_null() {
+                    let owned = unsafe { generation.into_box() };
+                    generation = owned.old.load(Relaxed, guard);
+                    drop(owned);
+                }
+            },
+            Storage::Segmented(storage) => {
+                for spot in storage.spots(guard) {
+                    unsafe { drop_word::<T>(spot.load(Relaxed, guard), guard) };
+                }
+            },
+        }
+
+        for op in &self.thread_ops {
+            let opptr = op.load(Relaxed, guard);
+            if !opptr.is_null() {
+                unsafe { retire_op::<T>(opptr, guard) };
+            }
+        }
+
+        for pool in &mut self.values {
+            unsafe { pool.get_mut().unwrap_or_else(PoisonError::into_inner).free::<T>() };
         }
     }
 }
+
+// Moves the values read from `start` on into `out`, and tells which
+// positions had nothing given the vector was `length` long.
+fn fill_range<T>(start: usize, length: usize, values: Vec<Option<T>>, out: &mut [T]) -> Vec<RangeSlot> {
+    values.into_iter().zip(out.iter_mut()).enumerate().map(|(i, (value, slot))| match value {
+        Some(value) => {
+            *slot = value;
+            RangeSlot::Read
+        },
+        None if start + i < length => RangeSlot::Empty,
+        None => RangeSlot::BeyondSize,
+    }).collect()
+}
+
+// Frees what a spot of a dropped vector holds. Operations finish their
+// descriptors before returning, so one is only left behind by an operation
+// that panicked, and then the value it was moving goes with it.
+unsafe fn drop_word<T>(word: MarkedPtr<usize>, guard: &Guard) {
+    match SlotWord::decode(word) {
+        SlotWord::Value(value) => value.retire::<T>(guard),
+        // The word may be frozen, so look at it with the resize bit off.
+        SlotWord::Descr(_) => {
+            let descr = match unpack_descr(word.with_mark(TagDescr), guard) {
+                Some(descr) => release_descr(descr),
+                None => return,
+            };
+
+            match &*descr {
+                BaseDescr::PushDescrType(d) if d.owner.is_none() => d.value.retire::<T>(guard),
+                BaseDescr::PopSubDescrType(d) => {
+                    if let Some(value) = d.value {
+                        value.retire::<T>(guard);
+                    }
+                },
+                BaseDescr::WriteDescrType(d) => {
+                    d.old.retire::<T>(guard);
+                    d.new.retire::<T>(guard);
+                },
+                // A cas_multi that passed no longer frees its new values.
+                BaseDescr::MultiDescrType(d) => {
+                    d.found.retire::<T>(guard);
+                    if d.writes() {
+                        d.entry().new.retire::<T>(guard);
+                    }
+                },
+                _ => (),
+            }
+        },
+        SlotWord::NotValue | SlotWord::NotCopied => (),
+    }
+}
+
+impl workload::ConcurrentVector for WaitFreeVector<usize> {
+    fn with_threads(capacity: usize, num_threads: usize) -> Self {
+        WaitFreeVector::new(capacity, num_threads)
+    }
+
+    fn push_back(&self, tid: usize, value: usize) {
+        WaitFreeVector::push_back(self, tid, value)
+    }
+
+    fn pop_back(&self, tid: usize) -> Option<usize> {
+        WaitFreeVector::pop_back(self, tid)
+    }
+
+    fn at(&self, tid: usize, pos: usize) -> Option<usize> {
+        WaitFreeVector::at(self, tid, pos)
+    }
+
+    fn cwrite(&self, tid: usize, pos: usize, old: usize, new: usize) -> bool {
+        WaitFreeVector::cwrite(self, tid, pos, old, new)
+    }
+
+    fn length(&self) -> usize {
+        WaitFreeVector::length(self)
+    }
+}
@@ -0,0 +1,198 @@
+// What an unsafe fn requires of its caller is in the comment above it, like
+// everywhere else in the crate, rather than in a rustdoc Safety section.
+#![allow(clippy::missing_safety_doc)]
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::align_of;
+use std::ptr;
+use std::sync::atomic::Ordering;
+
+use crate::epoch::Guard;
+use crate::sync::AtomicPtr;
+
+// An atomic pointer that keeps a mark in the low bits left free by the
+// alignment of T, like crossbeam's Atomic does with its tag. Unlike
+// crossbeam it stores an actual pointer rather than a usize, so marking and
+// unmarking keep the pointer's provenance. Every pointer the vector shares
+// goes through one of these, and epoch.rs keeps pointers as pointers too,
+// so no integer is ever turned back into one. The tests run under Miri with
+//
+//     MIRIFLAGS="-Zmiri-strict-provenance" cargo +nightly miri test -p waitfree-rust
+//
+// Loads borrow a guard of epoch.rs: what they return may be
+// retired by another thread in the meantime, but it is not freed while the
+// guard is pinned.
+pub struct AtomicMarkablePtr<T> {
+    ptr: AtomicPtr<T>,
+}
+
+// A pointer loaded from an AtomicMarkablePtr, together with its mark.
+pub struct MarkedPtr<'g, T> {
+    ptr: *mut T,
+    guard: PhantomData<(&'g (), *const T)>,
+}
+
+impl<T> AtomicMarkablePtr<T> {
+    // The bits a mark can use. Every way of making an AtomicMarkablePtr or a
+    // MarkedPtr goes through this, so a T that leaves no bit free fails to
+    // compile instead of corrupting pointers at run time.
+    pub const MARK_MASK: usize = {
+        assert!(align_of::<T>() > 1, "T is not aligned enough to leave room for a mark");
+        align_of::<T>() - 1
+    };
+
+    pub const MARK_BITS: u32 = Self::MARK_MASK.count_ones();
+
+    pub fn null() -> AtomicMarkablePtr<T> {
+        AtomicMarkablePtr::from(MarkedPtr::null())
+    }
+
+    pub fn new(value: T) -> AtomicMarkablePtr<T> {
+        AtomicMarkablePtr::from(MarkedPtr::from_box(Box::new(value)))
+    }
+
+    pub fn load<'g>(&self, order: Ordering, _guard: &'g Guard) -> MarkedPtr<'g, T> {
+        MarkedPtr::new(self.ptr.load(order))
+    }
+
+    pub fn store(&self, new: MarkedPtr<T>, order: Ordering) {
+        self.ptr.store(new.ptr, order);
+    }
+
+    pub fn swap<'g>(&self, new: MarkedPtr<T>, order: Ordering, _guard: &'g Guard) -> MarkedPtr<'g, T> {
+        MarkedPtr::new(self.ptr.swap(new.ptr, order))
+    }
+
+    // Pointer and mark are compared together, so a CAS fails on a spot whose
+    // pointer is right but whose mark changed. Either way the word found is
+    // returned.
+    pub fn compare_exchange<'g>(
+        &self,
+        current: MarkedPtr<T>,
+        new: MarkedPtr<T>,
+        success: Ordering,
+        failure: Ordering,
+        _guard: &'g Guard,
+    ) -> Result<MarkedPtr<'g, T>, MarkedPtr<'g, T>> {
+        self.ptr.compare_exchange(current.ptr, new.ptr, success, failure)
+            .map(MarkedPtr::new)
+            .map_err(MarkedPtr::new)
+    }
+
+    // Sets the bits of `mark` whatever the pointer is, and returns the word
+    // as it was before.
+    pub fn mark<'g>(&self, mark: usize, order: Ordering, _guard: &'g Guard) -> MarkedPtr<'g, T> {
+        debug_assert_eq!(mark & !Self::MARK_MASK, 0, "mark does not fit in the free bits");
+        MarkedPtr::new(self.ptr.fetch_or(mark, order))
+    }
+
+    // Clears the bits of `mark` whatever the pointer is, and returns the word
+    // as it was before.
+    pub fn unmark<'g>(&self, mark: usize, order: Ordering, _guard: &'g Guard) -> MarkedPtr<'g, T> {
+        debug_assert_eq!(mark & !Self::MARK_MASK, 0, "mark does not fit in the free bits");
+        MarkedPtr::new(self.ptr.fetch_and(!mark, order))
+    }
+}
+
+impl<T> From<MarkedPtr<'_, T>> for AtomicMarkablePtr<T> {
+    fn from(ptr: MarkedPtr<T>) -> AtomicMarkablePtr<T> {
+        AtomicMarkablePtr { ptr: AtomicPtr::new(ptr.ptr) }
+    }
+}
+
+impl<T> fmt::Debug for AtomicMarkablePtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        MarkedPtr::<T>::new(self.ptr.load(Ordering::Acquire)).fmt(f)
+    }
+}
+
+impl<'g, T> MarkedPtr<'g, T> {
+    fn new(ptr: *mut T) -> MarkedPtr<'g, T> {
+        MarkedPtr { ptr, guard: PhantomData }
+    }
+
+    pub fn null() -> MarkedPtr<'g, T> {
+        MarkedPtr::from_raw(ptr::null())
+    }
+
+    // Leaks `value` into an unmarked pointer. It is freed again through
+    // into_box or defer_destroy.
+    pub fn from_box(value: Box<T>) -> MarkedPtr<'g, T> {
+        MarkedPtr::new(Box::into_raw(value))
+    }
+
+    pub fn from_raw(raw: *const T) -> MarkedPtr<'g, T> {
+        debug_assert_eq!(raw.addr() & AtomicMarkablePtr::<T>::MARK_MASK, 0, "pointer is not aligned");
+        MarkedPtr::new(raw.cast_mut())
+    }
+
+    pub fn is_null(self) -> bool {
+        self.as_raw().is_null()
+    }
+
+    pub fn mark(self) -> usize {
+        self.ptr.addr() & AtomicMarkablePtr::<T>::MARK_MASK
+    }
+
+    pub fn with_mark(self, mark: usize) -> MarkedPtr<'g, T> {
+        let mask = AtomicMarkablePtr::<T>::MARK_MASK;
+        debug_assert_eq!(mark & !mask, 0, "mark does not fit in the free bits");
+        MarkedPtr::new(self.ptr.map_addr(|addr| (addr & !mask) | mark))
+    }
+
+    // The pointer without its mark.
+    pub fn as_raw(self) -> *const T {
+        self.ptr.map_addr(|addr| addr & !AtomicMarkablePtr::<T>::MARK_MASK)
+    }
+
+    // Reinterprets the pointer, mark included, as one to a U. A U has to
+    // leave at least as many bits free as T so the mark still fits.
+    pub fn cast<U>(self) -> MarkedPtr<'g, U> {
+        const { assert!(align_of::<U>() >= align_of::<T>(), "casting to a less aligned type would lose the mark") };
+        MarkedPtr::new(self.ptr.cast())
+    }
+
+    // The pointer must point at a live T, which the guard keeps alive for 'g.
+    pub unsafe fn deref(self) -> &'g T {
+        &*self.as_raw()
+    }
+
+    // Takes back ownership of a pointer made by from_box that nobody else
+    // can reach any more.
+    pub unsafe fn into_box(self) -> Box<T> {
+        Box::from_raw(self.as_raw().cast_mut())
+    }
+
+    // Frees the T once no thread pinned right now can still be reading it.
+    // Only whoever unlinked the pointer may call this, and only once.
+    pub unsafe fn defer_destroy(self, guard: &Guard) {
+        let raw = self.as_raw().cast_mut();
+        guard.defer_unchecked(move || drop(Box::from_raw(raw)));
+    }
+}
+
+impl<T> Clone for MarkedPtr<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for MarkedPtr<'_, T> {}
+
+impl<T> PartialEq for MarkedPtr<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T> Eq for MarkedPtr<'_, T> {}
+
+impl<T> fmt::Debug for MarkedPtr<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MarkedPtr")
+            .field("raw", &self.as_raw())
+            .field("mark", &self.mark())
+            .finish()
+    }
+}
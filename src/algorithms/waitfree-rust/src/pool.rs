@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::Arc;
+
+use crate::epoch;
+use crate::reclaim::Reclaim;
+use crate::sync::{fence, AtomicUsize, Acquire, Relaxed};
+use crate::{BaseDescr, MarkedPtr, PopDescr, PushDescr, ValuePtr, POOL_SIZE, STATE_UNDECIDED};
+
+// Descriptors one thread places with push_back and pop_back, handed out
+// again and again. A spot, a helper and a retired word waiting for its epoch
+// each hold a reference to the descriptor, so the pool may only reuse one it
+// holds the last reference to. That also makes reuse as safe as freeing: a
+// thread still pinned from when the descriptor was in a spot keeps the
+// retired word's reference alive, and a CAS of its can never mistake the
+// descriptor's next use for the old one.
+//
+// A pool starts out with `preallocated` descriptors of each kind and keeps
+// whatever it has to allocate on top, up to POOL_SIZE. Past that, or when
+// every descriptor is still in use, it falls back to a fresh allocation.
+//
+// A fixed vector does not retire the descriptors its pools keep through
+// epoch.rs, whose bags allocate as they grow. It stamps them with an epoch of
+// its own instead and lets go of the spot's reference right away, so there
+// `safe` has to agree as well: nobody pinned is left from the stamp on.
+pub(crate) struct DescrPool {
+    push: Vec<Arc<BaseDescr>>,
+    pop: Vec<Arc<BaseDescr>>,
+    // Descriptors are handed out round robin, so the one tried first is the
+    // one that has had the longest to come back.
+    next_push: usize,
+    next_pop: usize,
+}
+
+impl DescrPool {
+    pub(crate) fn new(preallocated: usize) -> DescrPool {
+        let push = (0..preallocated)
+            .map(|_| Arc::new(BaseDescr::PushDescrType(Arc::new(kept_push(0, ValuePtr::dangling())))))
+            .collect();
+        let pop = (0..preallocated)
+            .map(|_| Arc::new(BaseDescr::PopDescrType(PopDescr::pooled(0, true))))
+            .collect();
+
+        DescrPool { push, pop, next_push: 0, next_pop: 0 }
+    }
+
+    pub(crate) fn push_descr(&mut self, pos: usize, value: ValuePtr, safe: impl Fn(usize) -> bool) -> Arc<BaseDescr> {
+        let reused = reuse(&mut self.push, &mut self.next_push, |descr| match descr {
+            BaseDescr::PushDescrType(descr) => match Arc::get_mut(descr) {
+                Some(descr) if descr.retired.as_ref().is_none_or(|stamp| safe(stamp.load(Acquire))) => {
+                    let retired = descr.retired.take();
+                    *descr = PushDescr { retired, ..PushDescr::new(pos, value) };
+                    true
+                },
+                _ => false,
+            },
+            _ => false,
+        });
+
+        reused.unwrap_or_else(|| keep(&mut self.push, |kept| {
+            let descr = if kept { kept_push(pos, value) } else { PushDescr::new(pos, value) };
+            BaseDescr::PushDescrType(Arc::new(descr))
+        }))
+    }
+
+    pub(crate) fn pop_descr(&mut self, pos: usize, safe: impl Fn(usize) -> bool) -> Arc<BaseDescr> {
+        let reused = reuse(&mut self.pop, &mut self.next_pop, |descr| match descr {
+            BaseDescr::PopDescrType(descr) => reclaim_pop(descr, pos, &safe),
+            _ => false,
+        });
+
+        reused.unwrap_or_else(|| keep(&mut self.pop, |kept| BaseDescr::PopDescrType(PopDescr::pooled(pos, kept))))
+    }
+}
+
+fn kept_push(pos: usize, value: ValuePtr) -> PushDescr {
+    PushDescr { retired: Some(AtomicUsize::new(0)), ..PushDescr::new(pos, value) }
+}
+
+// Where a fixed vector stamps a descriptor it takes out of a spot, if a
+// pool keeps the descriptor. A claim is stamped on its own, since it may
+// leave its spot after its pop left its own.
+pub(crate) fn stamp_of(descr: &BaseDescr) -> Option<&AtomicUsize> {
+    match descr {
+        BaseDescr::PushDescrType(descr) => descr.retired.as_ref(),
+        BaseDescr::PopDescrType(descr) => descr.retired.as_ref(),
+        BaseDescr::PopSubDescrType(descr) => descr.retired.as_ref(),
+        _ => None,
+    }
+}
+
+// Hands out the first descriptor from `next` on that nobody else holds and
+// that `reset` manages to prepare for its next use.
+fn reuse(slots: &mut [Arc<BaseDescr>], next: &mut usize, mut reset: impl FnMut(&mut BaseDescr) -> bool) -> Option<Arc<BaseDescr>> {
+    let len = slots.len();
+
+    for _ in 0..len {
+        let slot = &mut slots[*next];
+        *next = (*next + 1) % len;
+
+        if Arc::get_mut(slot).is_some_and(&mut reset) {
+            return Some(slot.clone());
+        }
+    }
+
+    None
+}
+
+// A new descriptor, which the pool keeps if it has room. `make` learns
+// whether it does.
+fn keep(slots: &mut Vec<Arc<BaseDescr>>, make: impl FnOnce(bool) -> BaseDescr) -> Arc<BaseDescr> {
+    let kept = slots.len() < POOL_SIZE;
+    let descr = Arc::new(make(kept));
+    if kept {
+        slots.push(descr.clone());
+    }
+
+    descr
+}
+
+// What Arc::get_mut hands out, for an Arc whose other references, weak ones
+// included, nobody is going to use for as long as the borrow lasts. That is
+// the contract of the unstable Arc::get_mut_unchecked.
+#[allow(clippy::mut_from_ref)]
+pub(crate) unsafe fn exclusive<T>(arc: &Arc<T>) -> &mut T {
+    &mut *Arc::as_ptr(arc).cast_mut()
+}
+
+unsafe fn strong_count_raw<T>(raw: *const T) -> usize {
+    Arc::strong_count(&ManuallyDrop::new(Arc::from_raw(raw)))
+}
+
+// A used PopDescr is never unique by Arc::get_mut's standard, since its
+// claim and its child point back at it with a Weak. It can still be ours:
+// once the pool holds the only strong reference, and every weak reference
+// belongs to the claim or the child, which in turn nobody but the pop holds,
+// none of them can be reached any more except through the pool.
+//
+// The order of the checks matters. A weak reference is upgraded only by a
+// claim in complete_pop_sub, which holds the claim for as long as it holds
+// the pop. So a pop with a single strong reference first, and a claim and a
+// child with no other holders after that, had nobody upgrade in between
+// who is still around. The stamps come last, once nobody holds a reference
+// that could still lead to a new stamp.
+fn reclaim_pop(pop: &mut Arc<PopDescr>, pos: usize, safe: impl Fn(usize) -> bool) -> bool {
+    let guard = unsafe { epoch::unprotected() };
+
+    let claim = match pop.claim.as_deref() {
+        Some(BaseDescr::PopSubDescrType(sub)) => sub,
+        _ => return false,
+    };
+
+    if Arc::strong_count(pop) != 1 {
+        return false;
+    }
+    fence(Acquire);
+
+    let child = pop.child.load(Acquire, guard);
+    let settled_on_claim = ptr::eq(child.as_raw(), Arc::as_ptr(claim));
+    let other_child = !child.is_null() && !settled_on_claim;
+
+    if Arc::weak_count(pop) != 1 + other_child as usize {
+        return false;
+    }
+    fence(Acquire);
+
+    let claim_shell = pop.claim.as_ref().map_or(0, Arc::strong_count);
+    if claim_shell != 1 || Arc::strong_count(claim) != 1 + settled_on_claim as usize {
+        return false;
+    }
+    if other_child && unsafe { strong_count_raw(child.as_raw()) } != 1 {
+        return false;
+    }
+    fence(Acquire);
+
+    if pop.child.load(Acquire, guard) != child {
+        return false;
+    }
+
+    let stamped_safe = |stamp: Option<&AtomicUsize>| stamp.is_none_or(|stamp| safe(stamp.load(Acquire)));
+    if !stamped_safe(pop.retired.as_ref()) || !stamped_safe(claim.retired.as_ref()) {
+        return false;
+    }
+
+    let pop = unsafe { exclusive(pop) };
+
+    pop.child.store(MarkedPtr::null(), Relaxed);
+    if !child.is_null() {
+        drop(unsafe { Arc::from_raw(child.as_raw()) });
+    }
+
+    pop.pos = pos;
+    pop.claim_taken.store(false, Relaxed);
+
+    // With the child gone nobody else holds the claim.
+    let claim = match pop.claim.as_mut().and_then(Arc::get_mut) {
+        Some(BaseDescr::PopSubDescrType(sub)) => Arc::get_mut(sub).expect("a reclaimed claim has no other holders"),
+        _ => unreachable!(),
+    };
+    claim.pos = pos - 1;
+    claim.value = None;
+    claim.state = STATE_UNDECIDED;
+
+    true
+}
+
+// Boxes for the values one thread of a fixed vector pushes. A box whose
+// value a pop took waits in `retired` with the epoch it left the vector in,
+// and once nobody pinned can still read the value, the next push drops it
+// and puts its own in. A box that never made it into the vector comes back
+// empty. Like DescrPool it starts out with `preallocated` boxes and falls
+// back to allocating: a thread that pushes more than it pops runs dry, one
+// that pops more than it pushes runs full and retires through epoch.rs.
+pub(crate) struct ValuePool {
+    empty: Vec<ValuePtr>,
+    // Oldest first, so only the front can be safe before the rest.
+    retired: VecDeque<(ValuePtr, usize)>,
+}
+
+impl ValuePool {
+    pub(crate) fn new<T>(preallocated: usize) -> ValuePool {
+        let mut empty = Vec::with_capacity(POOL_SIZE.max(preallocated));
+        empty.extend((0..preallocated).map(|_| ValuePtr::empty::<T>()));
+
+        ValuePool { empty, retired: VecDeque::with_capacity(POOL_SIZE) }
+    }
+
+    // A box for the next value, and whether the old value is still in it.
+    pub(crate) fn take(&mut self, reclaim: &Reclaim) -> Option<(ValuePtr, bool)> {
+        match self.retired.front() {
+            Some(&(_, epoch)) if reclaim.is_safe(epoch) => self.retired.pop_front().map(|(value, _)| (value, true)),
+            _ => self.empty.pop().map(|value| (value, false)),
+        }
+    }
+
+    // Takes back a box that holds no value, unless the pool is full.
+    pub(crate) fn give_back(&mut self, value: ValuePtr) -> Result<(), ValuePtr> {
+        if self.empty.len() == self.empty.capacity() {
+            return Err(value);
+        }
+
+        self.empty.push(value);
+        Ok(())
+    }
+
+    // Takes a value that left the vector in `epoch`, unless the pool is full.
+    pub(crate) fn retire(&mut self, value: ValuePtr, epoch: usize) -> Result<(), ValuePtr> {
+        if self.retired.len() == POOL_SIZE {
+            return Err(value);
+        }
+
+        self.retired.push_back((value, epoch));
+        Ok(())
+    }
+
+    // Frees every box, for a vector that is being dropped.
+    pub(crate) unsafe fn free<T>(&mut self) {
+        for value in self.empty.drain(..) {
+            value.dealloc::<T>();
+        }
+        for (value, _) in self.retired.drain(..) {
+            value.free::<T>();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::ops::Deref;
+use crossbeam_utils::CachePadded;
+
+use crate::epoch::{self, Guard};
+use crate::sync::{fence, AtomicUsize, Acquire, Release, SeqCst};
+
+// Epochs of a fixed vector's own, for the descriptors and value boxes its
+// pools hand out again. Those of epoch.rs would do, except that every
+// retired object takes a slot in its thread's bag, which allocates as it
+// grows, and a fixed vector must not allocate once it runs.
+//
+// Every operation on the vector pins both epoch.rs and this. A pin counts
+// itself in its slot under the parity of the epoch it saw, and the epoch
+// moves on from `e` only once nobody is pinned under the parity of `e - 1`.
+// So whatever was retired in `e` is out of reach of every pin by `e + 2`.
+// A pin that sees the epoch move while it counts itself does not try again,
+// which could go on for as long as others keep moving it, but counts itself
+// under both parities and holds the epoch still until it unpins. Slots are
+// per tid only to spread the counting, several threads may share one.
+pub(crate) struct Reclaim {
+    epoch: CachePadded<AtomicUsize>,
+    pins: Vec<CachePadded<[AtomicUsize; 2]>>,
+}
+
+// Retired at epoch 0, the initial value of a descriptor's stamp, is safe
+// from the start.
+const FIRST_EPOCH: usize = 2;
+
+impl Reclaim {
+    pub(crate) fn new(slots: usize) -> Reclaim {
+        Reclaim {
+            epoch: CachePadded::new(AtomicUsize::new(FIRST_EPOCH)),
+            pins: (0..slots.max(1)).map(|_| CachePadded::new([AtomicUsize::new(0), AtomicUsize::new(0)])).collect(),
+        }
+    }
+
+    // Registers a pin in the slot for `tid`. Moves the epoch on first if it
+    // can, so a thread working alone still gets back what it retired two
+    // operations ago.
+    pub(crate) fn pin(&self, tid: usize) -> Pin {
+        self.try_advance();
+
+        let counts = &self.pins[tid % self.pins.len()];
+        let epoch = self.epoch.load(SeqCst);
+        counts[epoch & 1].fetch_add(1, SeqCst);
+        fence(SeqCst);
+
+        if self.epoch.load(SeqCst) == epoch {
+            return Pin::Parity(epoch & 1);
+        }
+
+        // The epoch moved on before we were counted, so whoever moved it may
+        // not have seen us, and the parity we are counted under may come
+        // round again.
+        counts[(epoch + 1) & 1].fetch_add(1, SeqCst);
+        fence(SeqCst);
+        Pin::Both
+    }
+
+    pub(crate) fn unpin(&self, tid: usize, pin: Pin) {
+        let counts = &self.pins[tid % self.pins.len()];
+        match pin {
+            Pin::Parity(parity) => {
+                counts[parity].fetch_sub(1, Release);
+            },
+            Pin::Both => {
+                counts[0].fetch_sub(1, Release);
+                counts[1].fetch_sub(1, Release);
+            },
+        }
+    }
+
+    fn try_advance(&self) {
+        let epoch = self.epoch.load(SeqCst);
+        let previous = (epoch + 1) & 1;
+
+        if self.pins.iter().all(|counts| counts[previous].load(SeqCst) == 0) {
+            let _ = self.epoch.compare_exchange(epoch, epoch + 1, SeqCst, SeqCst);
+        }
+    }
+
+    // The epoch to stamp an object with that was just taken out of the
+    // vector. Only its pool may hand it out again, once it is safe.
+    pub(crate) fn retire_epoch(&self) -> usize {
+        fence(SeqCst);
+        self.epoch.load(SeqCst)
+    }
+
+    // Whether nobody pinned can still see an object stamped `retired`.
+    pub(crate) fn is_safe(&self, retired: usize) -> bool {
+        self.epoch.load(Acquire) >= retired + 2
+    }
+}
+
+// What a pin is counted under.
+#[derive(Clone, Copy)]
+pub(crate) enum Pin {
+    Parity(usize),
+    Both,
+}
+
+// A guard of epoch.rs together with a pin of the vector's own, if it has
+// epochs. Everything that takes a &Guard takes a &Pinned as well.
+pub(crate) struct Pinned<'r> {
+    guard: Guard,
+    pin: Option<(&'r Reclaim, usize, Pin)>,
+}
+
+impl<'r> Pinned<'r> {
+    pub(crate) fn new(reclaim: Option<&'r Reclaim>, tid: usize) -> Pinned<'r> {
+        let pin = reclaim.map(|reclaim| (reclaim, tid, reclaim.pin(tid)));
+        Pinned { guard: epoch::pin(), pin }
+    }
+}
+
+impl Deref for Pinned<'_> {
+    type Target = Guard;
+
+    fn deref(&self) -> &Guard {
+        &self.guard
+    }
+}
+
+impl Drop for Pinned<'_> {
+    fn drop(&mut self) {
+        if let Some((reclaim, tid, pin)) = self.pin {
+            reclaim.unpin(tid, pin);
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::epoch::{self, Guard};
+use crate::sync::{AcqRel, Acquire, Relaxed};
+use crate::{make_spot, AtomicMarkablePtr, MarkedPtr, Spot, TagNotValue};
+
+// Size of bucket 0. Every following bucket doubles, so bucket i holds
+// FIRST_BUCKET_SIZE << i spots. Has to be a power of two.
+const FIRST_BUCKET_SIZE: usize = 8;
+const FIRST_BUCKET_BITS: u32 = FIRST_BUCKET_SIZE.trailing_zeros();
+
+// Enough buckets to address every usize position.
+const BUCKETS: usize = (usize::BITS - FIRST_BUCKET_BITS) as usize;
+
+// Two-level storage in the style of Dechev et al.: a fixed array of bucket
+// pointers where each bucket is allocated the first time a position in it is
+// needed. Nothing is ever copied, so a spot keeps its address for the whole
+// lifetime of the vector and there is no chain of old generations to follow.
+pub(crate) struct Segmented {
+    buckets: Vec<AtomicMarkablePtr<Vec<Spot>>>,
+}
+
+impl Segmented {
+    pub fn new(capacity: usize) -> Segmented {
+        let mut buckets = Vec::with_capacity(BUCKETS);
+
+        for _ in 0..BUCKETS {
+            buckets.push(AtomicMarkablePtr::null());
+        }
+
+        let storage = Segmented { buckets };
+        storage.reserve(capacity, &epoch::pin());
+
+        storage
+    }
+
+    // Allocates every bucket needed to hold `capacity` spots.
+    pub fn reserve(&self, capacity: usize, guard: &Guard) {
+        if capacity == 0 {
+            return;
+        }
+
+        let (last, _) = locate(capacity - 1);
+        for bucket in 0..=last {
+            self.get_bucket(bucket, guard);
+        }
+    }
+
+    // Number of spots in the buckets that have been allocated so far.
+    pub fn capacity(&self, guard: &Guard) -> usize {
+        self.buckets.iter()
+            .enumerate()
+            .filter(|(_, bucket)| !bucket.load(Acquire, guard).is_null())
+            .map(|(i, _)| bucket_size(i))
+            .sum()
+    }
+
+    pub fn get_spot(&self, position: usize, guard: &Guard) -> Spot {
+        let (bucket, index) = locate(position);
+        let spots = self.get_bucket(bucket, guard);
+
+        spots[index].clone()
+    }
+
+    // Every spot of the buckets allocated so far.
+    pub fn spots<'g>(&'g self, guard: &'g Guard) -> impl Iterator<Item = &'g Spot> {
+        self.buckets.iter()
+            .map(move |bucket| bucket.load(Acquire, guard))
+            .filter(|bucket| !bucket.is_null())
+            .flat_map(|bucket| unsafe { bucket.deref() }.iter())
+    }
+
+    fn get_bucket<'g>(&self, bucket: usize, guard: &'g Guard) -> &'g Vec<Spot> {
+        let current = self.buckets[bucket].load(Acquire, guard);
+        if !current.is_null() {
+            return unsafe { current.deref() };
+        }
+
+        let mut spots = Vec::with_capacity(bucket_size(bucket));
+        for _ in 0..bucket_size(bucket) {
+            let init: MarkedPtr<usize> = MarkedPtr::null().with_mark(TagNotValue);
+            spots.push(make_spot(init));
+        }
+
+        // Whoever loses the race drops its bucket and uses the winner's.
+        let spots = MarkedPtr::from_box(Box::new(spots));
+        match self.buckets[bucket].compare_exchange(MarkedPtr::null(), spots, AcqRel, Acquire, guard) {
+            Ok(_) => unsafe { spots.deref() },
+            Err(installed) => unsafe {
+                drop(spots.into_box());
+                installed.deref()
+            },
+        }
+    }
+}
+
+impl Drop for Segmented {
+    fn drop(&mut self) {
+        let guard = unsafe { epoch::unprotected() };
+
+        for bucket in &self.buckets {
+            let spots = bucket.load(Relaxed, guard);
+            if !spots.is_null() {
+                drop(unsafe { spots.into_box() });
+            }
+        }
+    }
+}
+
+fn bucket_size(bucket: usize) -> usize {
+    FIRST_BUCKET_SIZE << bucket
+}
+
+// Maps a position to (bucket, index within bucket). Shifting by
+// FIRST_BUCKET_SIZE makes bucket i start at position FIRST_BUCKET_SIZE * (2^i - 1),
+// so the bucket is just the highest set bit and the index the remaining bits.
+fn locate(position: usize) -> (usize, usize) {
+    let pos = position + FIRST_BUCKET_SIZE;
+    let hibit = usize::BITS - 1 - pos.leading_zeros();
+
+    ((hibit - FIRST_BUCKET_BITS) as usize, pos ^ (1 << hibit))
+}
@@ -0,0 +1,72 @@
+// The atomics the vector is built from. Under `--cfg loom` they are loom's,
+// so tests/loom.rs can explore the interleavings and memory orderings the
+// algorithm allows. The epochs in epoch.rs run on them as well.
+#[cfg(not(loom))]
+pub(crate) use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicU8, AtomicUsize};
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize};
+
+#[cfg(loom)]
+pub(crate) use self::loom_ptr::AtomicPtr;
+
+// The orderings the vector asks for, each derived from what the algorithm
+// needs where it is used. The seqcst feature turns every one of them into
+// SeqCst, so benches/waitfree.rs can compare the two.
+#[cfg(not(feature = "seqcst"))]
+pub(crate) use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+
+#[cfg(feature = "seqcst")]
+pub(crate) use self::seqcst::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+
+#[cfg(feature = "seqcst")]
+#[allow(non_upper_case_globals)]
+mod seqcst {
+    use std::sync::atomic::Ordering;
+
+    pub(crate) const AcqRel: Ordering = Ordering::SeqCst;
+    pub(crate) const Acquire: Ordering = Ordering::SeqCst;
+    pub(crate) const Relaxed: Ordering = Ordering::SeqCst;
+    pub(crate) const Release: Ordering = Ordering::SeqCst;
+    pub(crate) const SeqCst: Ordering = Ordering::SeqCst;
+}
+
+// Loom's AtomicPtr has neither fetch_or nor fetch_and, which mark and
+// unmark need. Both are a fetch_update here, which loom sees as one step.
+#[cfg(loom)]
+mod loom_ptr {
+    use std::ops::Deref;
+    use std::sync::atomic::Ordering;
+
+    pub(crate) struct AtomicPtr<T>(loom::sync::atomic::AtomicPtr<T>);
+
+    impl<T> AtomicPtr<T> {
+        pub(crate) fn new(ptr: *mut T) -> AtomicPtr<T> {
+            AtomicPtr(loom::sync::atomic::AtomicPtr::new(ptr))
+        }
+
+        pub(crate) fn fetch_or(&self, bits: usize, order: Ordering) -> *mut T {
+            self.fetch_map(|addr| addr | bits, order)
+        }
+
+        pub(crate) fn fetch_and(&self, bits: usize, order: Ordering) -> *mut T {
+            self.fetch_map(|addr| addr & bits, order)
+        }
+
+        fn fetch_map(&self, f: impl Fn(usize) -> usize, order: Ordering) -> *mut T {
+            let failure = match order {
+                Ordering::Release | Ordering::Relaxed => Ordering::Relaxed,
+                _ => Ordering::Acquire,
+            };
+            self.0.fetch_update(order, failure, |ptr| Some(ptr.map_addr(&f))).unwrap()
+        }
+    }
+
+    impl<T> Deref for AtomicPtr<T> {
+        type Target = loom::sync::atomic::AtomicPtr<T>;
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+use std::sync::Arc;
+
+use crate::epoch::Guard;
+use crate::markable::{AtomicMarkablePtr, MarkedPtr};
+use crate::sync::{AtomicPtr, AtomicUsize, AcqRel, Acquire, Relaxed, Release};
+use crate::{BaseDescr, Shrink, TagDescr, TagNotCopied, TagNotValue, TagResize};
+
+// A spot is a MarkedPtr<usize> whose three low bits are tags, so everything
+// a spot can point at needs three free bits as well.
+const _: () = assert!(AtomicMarkablePtr::<usize>::MARK_BITS >= 3, "spots need three free bits for their tags");
+const _: () = assert!(AtomicMarkablePtr::<BaseDescr>::MARK_BITS >= 3);
+const _: () = assert!(AtomicMarkablePtr::<Shrink>::MARK_BITS >= 3);
+
+// Every element lives in a box of its own, aligned so that a pointer to it
+// has the tag bits free whatever T is. The header comes first, so it can be
+// read without knowing T.
+#[repr(C, align(8))]
+struct Value<T> {
+    header: Header,
+    value: T,
+}
+
+// A pop moves the value it took out of its box, unless somebody is reading
+// the value right then: readers clone it or compare it in place, with
+// nothing to stop the pop from dropping it under them. So readers count
+// themselves in `state` while they look, and the pop marks the value TAKEN
+// there. Whichever comes second on that one word finds out. A reader that
+// finds the value taken leaves it alone, a pop that finds readers clones
+// the value instead with `copy`, which every reader sets before counting
+// itself. Readers only exist for T: Clone, so there always is one then.
+#[repr(C)]
+struct Header {
+    version: u64,
+    state: AtomicUsize,
+    copy: AtomicPtr<()>,
+}
+
+const TAKEN: usize = 1;
+// The value is gone from the box, which is left to free without it.
+const MOVED: usize = 2;
+const READER: usize = 4;
+
+impl Header {
+    fn new(version: u64) -> Header {
+        Header { version, state: AtomicUsize::new(0), copy: AtomicPtr::new(ptr::null_mut()) }
+    }
+}
+
+// What Header::copy points at, with T erased.
+type CopyInto = unsafe fn(ValuePtr, *mut ());
+
+unsafe fn copy_into<T: Clone>(value: ValuePtr, out: *mut ()) {
+    ptr::write(out.cast::<T>(), value.get::<T>().clone());
+}
+
+// The address of a boxed element with its type erased, so descriptors and
+// ops can carry it without being generic. It is a pointer all the way: spot
+// words are made from it by a cast, never from an integer, and so keep the
+// provenance of the box.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ValuePtr(*const usize);
+
+// A ValuePtr is only ever turned back into a T by the vector that boxed it,
+// and that requires T: Send + Sync.
+unsafe impl Send for ValuePtr {}
+unsafe impl Sync for ValuePtr {}
+
+impl ValuePtr {
+    pub(crate) fn new<T>(value: T, version: u64) -> ValuePtr {
+        ValuePtr(Box::into_raw(Box::new(Value { header: Header::new(version), value })).cast())
+    }
+
+    // A box of its own for a clone of the value in this one.
+    pub(crate) unsafe fn copy<T: Clone>(self, version: u64) -> ValuePtr {
+        ValuePtr::new(self.get::<T>().clone(), version)
+    }
+
+    // Stands in for a value in a descriptor that is not in use. It is never
+    // read or freed.
+    pub(crate) fn dangling() -> ValuePtr {
+        ValuePtr(ptr::dangling::<Value<u64>>().cast())
+    }
+
+    pub(crate) fn word<'g>(self) -> MarkedPtr<'g, usize> {
+        MarkedPtr::from_raw(self.0)
+    }
+
+    // The box must hold a T and must not have been freed yet. Only for a
+    // value no pop can take, see peek for one that is in the vector.
+    pub(crate) unsafe fn get<'g, T>(self) -> &'g T {
+        &(*self.0.cast::<Value<T>>()).value
+    }
+
+    unsafe fn header<'g>(self) -> &'g Header {
+        &*self.0.cast::<Header>()
+    }
+
+    // The box must not have been freed yet.
+    pub(crate) unsafe fn version(self) -> u64 {
+        self.header().version
+    }
+
+    // Runs `f` on a value that is or was in the vector, unless a pop took
+    // it first. The box must not have been freed yet.
+    pub(crate) unsafe fn peek<T: Clone, R>(self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        let value = self.hold::<T>()?;
+        let result = f(value);
+        self.header().state.fetch_sub(READER, Release);
+        Some(result)
+    }
+
+    // Like peek, for a reader that hands out the reference itself and so
+    // never gets to say it is done. It stays counted, and a pop of the
+    // value clones it.
+    pub(crate) unsafe fn hold<'g, T: Clone>(self) -> Option<&'g T> {
+        let header = self.header();
+        header.copy.store(copy_into::<T> as CopyInto as *mut (), Relaxed);
+
+        // One that comes too late leaves its count behind, nobody looks at
+        // it any more.
+        if header.state.fetch_add(READER, AcqRel) & TAKEN != 0 {
+            return None;
+        }
+
+        Some(self.get::<T>())
+    }
+
+    // Hands out the value of a box for the one pop that took it: the value
+    // itself if nobody is reading it, a clone otherwise. Either way free
+    // and clear know what is left in the box.
+    pub(crate) unsafe fn claim<T>(self) -> T {
+        let header = self.header();
+
+        let state = header.state.fetch_or(TAKEN, AcqRel);
+        if state < READER {
+            let value = ptr::read(self.get::<T>());
+            header.state.fetch_or(MOVED, Release);
+            return value;
+        }
+
+        let copy = mem::transmute::<*mut (), CopyInto>(header.copy.load(Relaxed));
+        let mut out = MaybeUninit::<T>::uninit();
+        copy(self, out.as_mut_ptr().cast());
+        out.assume_init()
+    }
+
+    fn moved(self) -> bool {
+        unsafe { self.header() }.state.load(Acquire) & MOVED != 0
+    }
+
+    // Frees a value that has left the vector once no pinned thread can still
+    // be reading it. With an unprotected guard that is right away.
+    pub(crate) unsafe fn retire<T>(self, guard: &Guard) {
+        guard.defer_unchecked(move || self.free::<T>());
+    }
+
+    // Frees a box nobody else can get at, and the value in it unless a pop
+    // moved it out.
+    pub(crate) unsafe fn free<T>(self) {
+        if self.moved() {
+            self.dealloc::<T>();
+        }
+        else {
+            drop(Box::from_raw(self.0.cast_mut().cast::<Value<T>>()));
+        }
+    }
+
+    // A box with no value in it yet, for a ValuePool to fill later.
+    pub(crate) fn empty<T>() -> ValuePtr {
+        ValuePtr(Box::into_raw(Box::new(MaybeUninit::<Value<T>>::uninit())).cast())
+    }
+
+    // Puts a value into a box that holds none and that nobody else can get at.
+    pub(crate) unsafe fn fill<T>(self, value: T, version: u64) {
+        ptr::write(self.0.cast_mut().cast::<Value<T>>(), Value { header: Header::new(version), value });
+    }
+
+    // Moves the value out of a box nobody else can get at and leaves it empty.
+    pub(crate) unsafe fn take<T>(self) -> T {
+        ptr::read(self.0.cast::<Value<T>>()).value
+    }
+
+    // Drops the value in a box nobody else can get at, unless a pop moved
+    // it out already, and leaves it empty.
+    pub(crate) unsafe fn clear<T>(self) {
+        if !self.moved() {
+            ptr::drop_in_place(self.0.cast_mut().cast::<Value<T>>());
+        }
+    }
+
+    // Frees a box that holds no value.
+    pub(crate) unsafe fn dealloc<T>(self) {
+        drop(Box::from_raw(self.0.cast_mut().cast::<MaybeUninit<Value<T>>>()));
+    }
+}
+
+// What a spot holds, read off the tags of its word. A frozen word reads the
+// same as before it was frozen; the resize bit only matters to whoever tries
+// to change the spot.
+pub(crate) enum SlotWord<'g> {
+    NotValue,
+    NotCopied,
+    Value(ValuePtr),
+    Descr(&'g BaseDescr),
+}
+
+impl<'g> SlotWord<'g> {
+    // `word` has to come from a spot loaded under the guard that 'g borrows.
+    pub(crate) unsafe fn decode(word: MarkedPtr<'g, usize>) -> SlotWord<'g> {
+        match word.mark() & !TagResize {
+            TagNotValue => SlotWord::NotValue,
+            TagNotCopied => SlotWord::NotCopied,
+            TagDescr => SlotWord::Descr(word.cast::<BaseDescr>().deref()),
+            _ if word.is_null() => SlotWord::NotValue,
+            _ => SlotWord::Value(ValuePtr(word.as_raw())),
+        }
+    }
+}
+
+pub fn pack_descr(descr: BaseDescr, _guard: &Guard) -> MarkedPtr<'_, usize> {
+    pack_shared(Arc::new(descr))
+}
+
+// A spot holds one reference to its descriptor, so a descriptor a pool hands
+// out again and again only comes back to the pool once no spot points at it.
+pub(crate) fn pack_shared<'g>(descr: Arc<BaseDescr>) -> MarkedPtr<'g, usize> {
+    MarkedPtr::from_raw(Arc::into_raw(descr)).cast::<usize>().with_mark(TagDescr)
+}
+
+pub fn unpack_descr<'g>(curr: MarkedPtr<usize>, _guard: &'g Guard) -> Option<MarkedPtr<'g, BaseDescr>> {
+    if curr.mark() == TagDescr {
+        Some(MarkedPtr::from_raw(curr.as_raw().cast::<BaseDescr>()))
+    }
+    else {
+        None
+    }
+}
+
+// Gives back the reference a spot held. `descr` has to come from pack_shared,
+// and every packed word is released once.
+pub(crate) unsafe fn release_descr(descr: MarkedPtr<BaseDescr>) -> Arc<BaseDescr> {
+    Arc::from_raw(descr.as_raw())
+}
@@ -0,0 +1,195 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::{epoch, CapacityExceeded, GrowthPolicy, WaitFreeVector};
+
+mod common;
+
+// Counts the allocations made by the current thread, so the tests of this
+// binary that run next to each other do not see each other's.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn fills_up_and_empties() {
+    let vec = WaitFreeVector::bounded(4, 1);
+    assert_eq!(vec.capacity(), 4);
+
+    for i in 0..4 {
+        assert_eq!(vec.try_push_back(0, i), Ok(()));
+    }
+    assert_eq!(vec.try_push_back(0, 4), Err(CapacityExceeded(4)));
+
+    // Nothing moves the storage of a bounded vector.
+    vec.resize();
+    vec.reserve(0, 10);
+    assert!(!vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 4);
+    assert_eq!(vec.generations(), 1);
+
+    for i in (0..4).rev() {
+        assert_eq!(vec.try_pop_back(0), Some(i));
+    }
+    assert_eq!(vec.try_pop_back(0), None);
+    assert!(!vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 4);
+}
+
+#[test]
+fn steady_state_allocates_only_values() {
+    const ROUNDS: usize = if cfg!(miri) { 100 } else { 10_000 };
+
+    let per_round = |vec: WaitFreeVector<usize>| {
+        for i in 0..ROUNDS {
+            vec.push_back(0, i);
+            vec.pop_back(0);
+        }
+
+        let before = allocations();
+        for i in 0..ROUNDS {
+            vec.push_back(0, i);
+            vec.pop_back(0);
+        }
+        (allocations() - before) as f64 / ROUNDS as f64
+    };
+
+    // A bounded vector takes descriptors and value boxes from its pools and
+    // retires them by its own epochs, so it allocates nothing at all.
+    assert_eq!(per_round(WaitFreeVector::bounded(8, 1)), 0.0);
+
+    // The others still box every pushed value and retire through the
+    // epochs in epoch.rs, whose bags grow every so often. A descriptor they
+    // retire comes back to the pool only three collects, a few hundred pins,
+    // later, more than the rounds Miri gets through.
+    if cfg!(miri) {
+        return;
+    }
+    for (name, vec) in [("growing", WaitFreeVector::new(8, 1)), ("segmented", WaitFreeVector::new_segmented(8, 1))] {
+        let allocations = per_round(vec);
+        assert!(allocations < 1.5, "{}: {} allocations per push and pop", name, allocations);
+    }
+}
+
+// Spots past the one a pop on a full vector uses do not exist and never
+// will, asking for them is not a reason to panic.
+#[test]
+fn spots_out_of_reach_are_none() {
+    let guard = &epoch::pin();
+
+    let vec = WaitFreeVector::<usize>::bounded(4, 1);
+    assert!(vec.get_spot(4, guard).is_some());
+    assert!(vec.get_spot(5, guard).is_none());
+    assert!(vec.load_spot(usize::MAX, guard).is_none());
+
+    let vec = WaitFreeVector::<usize>::with_growth_policy(2, 1, GrowthPolicy::Bounded(4));
+    assert!(vec.load_spot(4, guard).is_some());
+    assert!(vec.load_spot(5, guard).is_none());
+    assert_eq!(vec.capacity(), 4);
+}
+
+// A push that never got a descriptor into a spot hands back the very value
+// it was given, not a copy.
+#[test]
+fn a_rejected_push_gives_back_the_original() {
+    let vec = WaitFreeVector::bounded(1, 1);
+    vec.push_back(0, "in".to_string());
+
+    let value = "out".to_string();
+    let buffer = value.as_ptr();
+
+    let CapacityExceeded(back) = vec.try_push_back(0, value).unwrap_err();
+    assert_eq!(back.as_ptr(), buffer);
+
+    let back = vec.try_push_at(0, 0, back).unwrap_err();
+    assert_eq!(back.as_ptr(), buffer);
+}
+
+#[test]
+fn threads_share_the_capacity() {
+    let num_threads = 4;
+    let max = 100;
+    let times = common::times(2000);
+
+    let vec = Arc::new(WaitFreeVector::bounded(max, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut held = 0;
+
+            for j in 0..times {
+                if j % 3 == 2 {
+                    held -= vec_thread.try_pop_back(i).is_some() as isize;
+                }
+                else {
+                    held += vec_thread.try_push_back(i, j).is_ok() as isize;
+                }
+            }
+
+            held
+        }));
+    }
+
+    let held: isize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(vec.length() as isize, held);
+    assert!(vec.length() <= max);
+    assert_eq!(vec.snapshot(0).len(), vec.length());
+    assert_eq!(vec.generations(), 1);
+}
+
+// Readers clone values while other threads pop them and push new ones into
+// the same boxes. A box reused before the last reader let go shows up as a
+// torn or freed string.
+#[test]
+fn readers_never_see_a_reused_box() {
+    let writers = 2;
+    let readers = 2;
+    let times = common::times(20_000);
+
+    let vec = Arc::new(WaitFreeVector::bounded(4, writers + readers));
+    let mut handles = Vec::new();
+
+    for tid in 0..writers + readers {
+        let vec = vec.clone();
+        handles.push(thread::spawn(move || {
+            for i in 0..times {
+                if tid < writers {
+                    let _ = vec.try_push_back(tid, format!("{}:{}", tid, i).repeat(4));
+                    vec.try_pop_back(tid);
+                }
+                else if let Some(value) = vec.at(tid, i % 4) {
+                    let (first, rest) = value.split_at(value.len() / 4);
+                    assert_eq!(rest, first.repeat(3), "read {:?}", value);
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
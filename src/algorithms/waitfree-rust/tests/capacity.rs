@@ -0,0 +1,170 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn reserve_grows_in_one_migration() {
+    let vec = WaitFreeVector::new(4, 1);
+
+    for i in 0..4 {
+        vec.push_back(0, i);
+    }
+
+    vec.reserve(0, 1000);
+    assert_eq!(vec.capacity(), 1004);
+    assert_eq!(vec.generations(), 1);
+
+    for i in 4..1004 {
+        vec.push_back(0, i);
+    }
+
+    // Everything fit, so no resize happened on the way.
+    assert_eq!(vec.capacity(), 1004);
+    for i in 0..1004 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+}
+
+#[test]
+fn reserve_never_shrinks() {
+    let vec = WaitFreeVector::new(64, 1);
+    vec.push_back(0, 1);
+
+    vec.reserve(0, 10);
+    assert_eq!(vec.capacity(), 64);
+}
+
+#[test]
+fn reserve_segmented_allocates_buckets() {
+    let vec = WaitFreeVector::<usize>::new_segmented(0, 1);
+    assert_eq!(vec.capacity(), 0);
+
+    vec.reserve(0, 100);
+    // Buckets of 8, 16, 32 and 64 spots.
+    assert_eq!(vec.capacity(), 120);
+    assert!(!vec.shrink_to_fit(0));
+}
+
+#[test]
+fn shrink_to_fit_keeps_values() {
+    let vec = WaitFreeVector::new(1000, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+
+    // One spot is kept above the last element.
+    assert!(vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 11);
+    assert_eq!(vec.length(), 10);
+    assert_eq!(vec.generations(), 1);
+
+    for i in 0..10 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+
+    // Growing again after a shrink goes back to doubling.
+    vec.push_back(0, 10);
+    assert_eq!(vec.capacity(), 11);
+    vec.push_back(0, 11);
+    assert_eq!(vec.capacity(), 23);
+    assert_eq!(vec.at(0, 11), Some(11));
+}
+
+// A pop places its descriptor right above the last element, which is the
+// spot a shrink keeps, so it does not grow the storage back.
+#[test]
+fn pop_after_shrink_to_fit() {
+    let vec = WaitFreeVector::new(1000, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+
+    assert!(vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 11);
+
+    for i in (0..10).rev() {
+        assert_eq!(vec.pop_back(0), Some(i));
+        assert_eq!(vec.capacity(), 11);
+    }
+    assert_eq!(vec.pop_back(0), None);
+
+    assert_eq!(vec.capacity(), 11);
+    assert_eq!(vec.generations(), 1);
+}
+
+#[test]
+fn shrink_to_fit_after_pops() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..100 {
+        vec.push_back(0, i);
+    }
+    for i in (10..100).rev() {
+        assert_eq!(vec.pop_back(0), Some(i));
+    }
+
+    assert_eq!(vec.capacity(), 127);
+    assert!(vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 11);
+    assert_eq!(vec.pop_back(0), Some(9));
+    assert_eq!(vec.capacity(), 11);
+    vec.push_back(0, 9);
+
+    for i in 0..10 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+}
+
+#[test]
+fn shrink_to_fit_when_already_full() {
+    let vec = WaitFreeVector::new(3, 1);
+
+    for i in 0..3 {
+        vec.push_back(0, i);
+    }
+
+    assert!(!vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 3);
+}
+
+#[test]
+fn shrink_to_fit_an_empty_vector() {
+    let vec = WaitFreeVector::new(16, 1);
+
+    assert!(vec.shrink_to_fit(0));
+    assert_eq!(vec.capacity(), 1);
+    assert!(!vec.shrink_to_fit(0));
+
+    vec.push_back(0, 7);
+    assert_eq!(vec.at(0, 0), Some(7));
+}
+
+#[test]
+fn threaded_shrink_while_pushing() {
+    let num_threads = 4;
+    let times = common::times(200);
+
+    let vec = Arc::new(WaitFreeVector::new(4096, num_threads));
+    let pushers = common::spawn_pushers(&vec, num_threads - 1, times);
+
+    let vec_thread = vec.clone();
+    let shrinker = thread::spawn(move || {
+        for _ in 0..50 {
+            vec_thread.shrink_to_fit(num_threads - 1);
+            thread::yield_now();
+        }
+    });
+
+    common::join(pushers);
+    shrinker.join().unwrap();
+
+    let total = (num_threads - 1) * times;
+    assert_eq!(vec.length(), total);
+
+    let seen = (0..total).map(|pos| vec.at(0, pos).unwrap()).collect();
+    common::assert_all_pushed(seen, num_threads - 1, times);
+}
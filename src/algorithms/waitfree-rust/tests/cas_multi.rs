@@ -0,0 +1,124 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn all_or_nothing() {
+    let vec = WaitFreeVector::new(4, 1);
+    for i in 0..4 {
+        vec.push_back(0, i);
+    }
+
+    assert!(vec.cas_multi(0, &[(3, 3, 30), (0, 0, 10)]));
+    assert_eq!(vec.snapshot(0), vec![10, 1, 2, 30]);
+
+    // One position off and nothing changes.
+    assert!(!vec.cas_multi(0, &[(1, 1, 11), (2, 5, 12)]));
+    assert!(!vec.cas_multi(0, &[(1, 1, 11), (4, 4, 14)]));
+    assert_eq!(vec.snapshot(0), vec![10, 1, 2, 30]);
+
+    assert!(vec.cas_multi(0, &[]));
+    assert_eq!(vec.length(), 4);
+}
+
+#[test]
+#[should_panic(expected = "cas_multi got a position twice")]
+fn a_position_only_once() {
+    let vec = WaitFreeVector::new(2, 1);
+    vec.push_back(0, 1);
+
+    vec.cas_multi(0, &[(0, 1, 2), (0, 1, 3)]);
+}
+
+#[test]
+fn values_survive_resizes_and_pops() {
+    for vec in [WaitFreeVector::new(2, 1), WaitFreeVector::new_segmented(2, 1)] {
+        for i in 0..2 {
+            vec.push_back(0, i.to_string());
+        }
+
+        let swap = [(0, "0".to_string(), "1".to_string()), (1, "1".to_string(), "0".to_string())];
+        assert!(vec.cas_multi(0, &swap));
+
+        for i in 2..50 {
+            vec.push_back(0, i.to_string());
+        }
+        assert_eq!(vec.at(0, 0), Some("1".to_string()));
+        assert_eq!(vec.at(0, 1), Some("0".to_string()));
+
+        for _ in 0..49 {
+            vec.pop_back(0);
+        }
+        assert!(!vec.cas_multi(0, &[(0, "1".to_string(), "2".to_string()), (1, "0".to_string(), "3".to_string())]));
+        assert_eq!(vec.snapshot(0), vec!["1".to_string()]);
+    }
+}
+
+// Every thread moves one unit from one position to another at a time, each
+// move a cas_multi on both. Moves that overlap run into each other's
+// descriptors, and still no unit gets lost or made up.
+#[test]
+fn transfers_keep_the_total() {
+    let num_threads = 4;
+    let accounts = 4;
+    let times = common::times(2000);
+
+    let vec = Arc::new(WaitFreeVector::new(accounts, num_threads));
+    for _ in 0..accounts {
+        vec.push_back(0, 100);
+    }
+
+    let mut handles = Vec::new();
+    for tid in 0..num_threads {
+        let vec = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut moved = 0;
+
+            for i in 0..times {
+                let from = (tid + i) % accounts;
+                let to = (from + 1 + i % (accounts - 1)) % accounts;
+
+                let (a, b) = (vec.at(tid, from).unwrap(), vec.at(tid, to).unwrap());
+                if a > 0 && vec.cas_multi(tid, &[(from, a, a - 1), (to, b, b + 1)]) {
+                    moved += 1;
+                }
+            }
+
+            moved
+        }));
+    }
+
+    let moved: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert!(moved > 0);
+    assert_eq!(vec.snapshot(0).iter().sum::<usize>(), 100 * accounts);
+}
+
+// A push is counted before its descriptor leaves the spot, so the last
+// counted position may still hold a push that has passed. That is the value
+// as far as cas_multi is concerned.
+#[test]
+fn sees_counted_pushes() {
+    let times = common::times(20_000);
+    let vec = Arc::new(WaitFreeVector::new(1, 2));
+
+    let pusher = {
+        let vec = vec.clone();
+        thread::spawn(move || {
+            for i in 0..times {
+                vec.push_back(0, i);
+            }
+        })
+    };
+
+    while vec.length() < times {
+        let n = vec.length();
+        if n > 0 {
+            assert!(vec.cas_multi(1, &[(n - 1, n - 1, n - 1)]), "index {} holds its own index", n - 1);
+        }
+    }
+
+    pusher.join().unwrap();
+}
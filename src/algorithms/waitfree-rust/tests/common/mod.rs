@@ -0,0 +1,66 @@
+// What the threaded tests share. Every test file that needs it declares
+// `mod common;`, and most of them use only part of it.
+#![allow(dead_code)]
+
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use waitfree_rust::WaitFreeVector;
+
+// How often each thread repeats its operation: `n` times, or 10 under Miri,
+// where every operation is slower by orders of magnitude.
+pub fn times(n: usize) -> usize {
+    if cfg!(miri) { 10 } else { n }
+}
+
+// The value pusher `i` pushes `j`th. Values tell their pusher apart, see
+// pusher, and come in order per pusher.
+pub fn value(i: usize, j: usize) -> usize {
+    i * 1000 + j
+}
+
+pub fn pusher(value: usize) -> usize {
+    value / 1000
+}
+
+// Starts `num_threads` pushers with tids 0 to num_threads - 1, each pushing
+// value(i, j) for j from 0 to `times`. Higher tids are free for whatever
+// runs next to them.
+pub fn spawn_pushers(vec: &Arc<WaitFreeVector>, num_threads: usize, times: usize) -> Vec<JoinHandle<()>> {
+    (0..num_threads).map(|i| {
+        let vec_thread = vec.clone();
+        thread::spawn(move || {
+            for j in 0..times {
+                vec_thread.push_back(i, value(i, j));
+            }
+        })
+    }).collect()
+}
+
+pub fn join(handles: Vec<JoinHandle<()>>) {
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+// Everything the pushers pushed, in the order sorting puts it in.
+pub fn pushed(num_threads: usize, times: usize) -> Vec<usize> {
+    let mut expected: Vec<usize> = (0..num_threads).flat_map(|i| (0..times).map(move |j| value(i, j))).collect();
+    expected.sort_unstable();
+    expected
+}
+
+// Checks that `seen` holds every value the pushers pushed exactly once.
+pub fn assert_all_pushed(mut seen: Vec<usize>, num_threads: usize, times: usize) {
+    seen.sort_unstable();
+    assert_eq!(seen, pushed(num_threads, times));
+}
+
+// Checks that `values` holds the first few values of every pusher, which is
+// what any state the vector really was in looks like while nothing is popped.
+pub fn assert_prefixes(values: &[usize], num_threads: usize) {
+    for i in 0..num_threads {
+        let mut mine: Vec<usize> = values.iter().copied().filter(|&v| pusher(v) == i).collect();
+        mine.sort_unstable();
+        assert_eq!(mine, (0..mine.len()).map(|j| value(i, j)).collect::<Vec<_>>(), "values of thread {} are not a prefix", i);
+    }
+}
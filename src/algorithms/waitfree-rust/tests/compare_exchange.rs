@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn reports_what_it_found() {
+    let vec = WaitFreeVector::new(2, 1);
+    vec.push_back(0, 1);
+
+    assert_eq!(vec.compare_exchange(0, 0, 1, 2), Ok(1));
+    assert_eq!(vec.compare_exchange(0, 0, 1, 3), Err(Some(2)));
+    assert_eq!(vec.compare_exchange(0, 1, 1, 3), Err(None));
+    assert_eq!(vec.compare_exchange(0, 5, 1, 3), Err(None));
+
+    vec.pop_back(0);
+    assert_eq!(vec.compare_exchange(0, 0, 2, 3), Err(None));
+}
+
+#[test]
+fn owned_values_come_back_intact() {
+    let vec = WaitFreeVector::new(2, 1);
+    vec.push_back(0, "one".to_string());
+
+    assert_eq!(vec.compare_exchange(0, 0, "two".to_string(), "three".to_string()), Err(Some("one".to_string())));
+    assert_eq!(vec.compare_exchange(0, 0, "one".to_string(), "two".to_string()), Ok("one".to_string()));
+    assert_eq!(vec.at(0, 0), Some("two".to_string()));
+}
+
+// Every thread adds one at a time, retrying with whatever value it was
+// told it lost to, so no increment gets lost and none is applied twice.
+#[test]
+fn increments_from_the_observed_value() {
+    let num_threads = 4;
+    let times = common::times(1000);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    vec.push_back(0, 0);
+
+    let mut handles = Vec::new();
+    for tid in 0..num_threads {
+        let vec = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut seen = vec.at(tid, 0).unwrap();
+            for _ in 0..times {
+                loop {
+                    match vec.compare_exchange(tid, 0, seen, seen + 1) {
+                        Ok(previous) => {
+                            assert_eq!(previous, seen);
+                            seen += 1;
+                            break;
+                        },
+                        Err(found) => seen = found.expect("the value never goes away"),
+                    }
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(vec.at(0, 0), Some(num_threads * times));
+}
@@ -0,0 +1,205 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicIsize, AtomicUsize};
+use std::sync::atomic::Ordering::SeqCst;
+use std::thread;
+use waitfree_rust::{epoch, WaitFreeVector};
+
+mod common;
+
+// Counts the copies of a value that are alive. A value dropped twice would
+// drive the count below what is expected, one never dropped keeps it above.
+struct Tracked {
+    id: usize,
+    live: Arc<AtomicIsize>,
+}
+
+impl Tracked {
+    fn new(id: usize, live: &Arc<AtomicIsize>) -> Tracked {
+        live.fetch_add(1, SeqCst);
+        Tracked { id, live: live.clone() }
+    }
+}
+
+impl Clone for Tracked {
+    fn clone(&self) -> Tracked {
+        Tracked::new(self.id, &self.live)
+    }
+}
+
+impl PartialEq for Tracked {
+    fn eq(&self, other: &Tracked) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, SeqCst);
+    }
+}
+
+// Values leaving the vector are freed through the epoch collector, so keep
+// nudging it until the count drops to `expected` or clearly will not.
+fn live_after_collect(live: &AtomicIsize, expected: isize) -> isize {
+    for _ in 0..10000 {
+        if live.load(SeqCst) == expected {
+            break;
+        }
+        epoch::pin().flush();
+    }
+    live.load(SeqCst)
+}
+
+#[test]
+fn strings_round_trip() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..20 {
+        vec.push_back(0, format!("value {}", i));
+    }
+
+    assert_eq!(vec.at(0, 3), Some("value 3".to_string()));
+    assert!(vec.cwrite(0, 3, "value 3".to_string(), "three".to_string()));
+    assert!(!vec.cwrite(0, 3, "value 3".to_string(), "again".to_string()));
+    assert_eq!(vec.at(0, 3), Some("three".to_string()));
+
+    assert_eq!(vec.pop_back(0), Some("value 19".to_string()));
+    assert_eq!(vec.snapshot(0).len(), 19);
+}
+
+#[test]
+fn pop_drops_the_value_once() {
+    let live = Arc::new(AtomicIsize::new(0));
+    let vec = WaitFreeVector::new(4, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, Tracked::new(i, &live));
+    }
+    for i in (5..10).rev() {
+        assert_eq!(vec.pop_back(0).map(|v| v.id), Some(i));
+    }
+
+    assert_eq!(live_after_collect(&live, 5), 5);
+
+    drop(vec);
+    assert_eq!(live_after_collect(&live, 0), 0);
+}
+
+// Cannot be cloned, so a pop has to hand out the value that was pushed.
+// Counts the drops of every id.
+struct Unique {
+    id: usize,
+    drops: Arc<Vec<AtomicUsize>>,
+}
+
+impl Drop for Unique {
+    fn drop(&mut self) {
+        self.drops[self.id].fetch_add(1, SeqCst);
+    }
+}
+
+#[test]
+fn pop_moves_the_value_out() {
+    let drops = Arc::new((0..10).map(|_| AtomicUsize::new(0)).collect::<Vec<_>>());
+    let vec = WaitFreeVector::new(4, 1);
+
+    for id in 0..10 {
+        vec.push_back(0, Unique { id, drops: drops.clone() });
+    }
+
+    // The popped value is dropped by us, right away, and the box it came
+    // from never drops it again.
+    for id in (5..10).rev() {
+        let popped = vec.pop_back(0).unwrap();
+        assert_eq!(popped.id, id);
+        assert_eq!(drops[id].load(SeqCst), 0);
+
+        drop(popped);
+        assert_eq!(drops[id].load(SeqCst), 1);
+    }
+
+    drop(vec);
+    for _ in 0..10000 {
+        if drops.iter().all(|count| count.load(SeqCst) > 0) {
+            break;
+        }
+        epoch::pin().flush();
+    }
+
+    for (id, count) in drops.iter().enumerate() {
+        assert_eq!(count.load(SeqCst), 1, "id {} dropped {} times", id, count.load(SeqCst));
+    }
+}
+
+#[test]
+fn cwrite_drops_the_replaced_value() {
+    let live = Arc::new(AtomicIsize::new(0));
+    let vec = WaitFreeVector::new(4, 1);
+
+    vec.push_back(0, Tracked::new(1, &live));
+    vec.push_back(0, Tracked::new(2, &live));
+
+    assert!(vec.cwrite(0, 0, Tracked::new(1, &live), Tracked::new(3, &live)));
+    assert!(!vec.cwrite(0, 1, Tracked::new(1, &live), Tracked::new(4, &live)));
+    assert_eq!(vec.at(0, 0).map(|v| v.id), Some(3));
+
+    assert_eq!(live_after_collect(&live, 2), 2);
+
+    drop(vec);
+    assert_eq!(live_after_collect(&live, 0), 0);
+}
+
+#[test]
+fn drop_frees_what_is_left() {
+    let live = Arc::new(AtomicIsize::new(0));
+
+    // Dropped in the middle of a resize: part of it still sits in the old generation.
+    let vec = WaitFreeVector::new(2000, 1);
+    for i in 0..2000 {
+        vec.push_back(0, Tracked::new(i, &live));
+    }
+    vec.resize();
+    assert_eq!(vec.generations(), 2);
+    drop(vec);
+
+    let vec = WaitFreeVector::new_segmented(0, 1);
+    for i in 0..50 {
+        vec.push_back(0, Tracked::new(i, &live));
+    }
+    drop(vec);
+
+    assert_eq!(live_after_collect(&live, 0), 0);
+}
+
+#[test]
+fn threaded_push_and_pop_drop_everything_once() {
+    let num_threads = 4;
+    let times = common::times(200);
+
+    let live = Arc::new(AtomicIsize::new(0));
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        let live = live.clone();
+        handles.push(thread::spawn(move || {
+            let mut popped = 0;
+            for j in 0..times {
+                vec_thread.push_back(i, Tracked::new(common::value(i, j), &live));
+                if j % 2 == 1 && vec_thread.pop_back(i).is_some() {
+                    popped += 1;
+                }
+            }
+            popped
+        }));
+    }
+
+    let popped: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    let left = (num_threads * times - popped) as isize;
+
+    assert_eq!(live_after_collect(&live, left), left);
+
+    drop(vec);
+    assert_eq!(live_after_collect(&live, 0), 0);
+}
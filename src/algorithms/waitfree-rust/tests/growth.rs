@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::{CapacityExceeded, GrowthPolicy, WaitFreeVector};
+
+mod common;
+
+#[test]
+fn half_grows_by_half() {
+    let vec = WaitFreeVector::with_growth_policy(4, 1, GrowthPolicy::Half);
+
+    for i in 0..5 {
+        vec.push_back(0, i);
+    }
+    assert_eq!(vec.capacity(), 4 + 2 + 1);
+
+    vec.resize();
+    assert_eq!(vec.capacity(), 7 + 3 + 1);
+    assert_eq!(vec.snapshot(0), (0..5).collect::<Vec<_>>());
+}
+
+#[test]
+fn jump_to_goes_straight_to_the_target() {
+    let vec = WaitFreeVector::with_growth_policy(1, 1, GrowthPolicy::JumpTo(1000));
+
+    vec.push_back(0, 0);
+    vec.push_back(0, 1);
+    assert_eq!(vec.capacity(), 1000);
+
+    for i in 2..1001 {
+        vec.push_back(0, i);
+    }
+    assert_eq!(vec.capacity(), 2001);
+    assert_eq!(vec.length(), 1001);
+}
+
+#[test]
+fn bounded_refuses_to_grow_past_its_maximum() {
+    let vec = WaitFreeVector::with_growth_policy(2, 1, GrowthPolicy::Bounded(5));
+
+    for i in 0..5 {
+        assert_eq!(vec.try_push_back(0, i), Ok(()));
+    }
+    assert_eq!(vec.try_push_back(0, 5), Err(CapacityExceeded(5)));
+    assert_eq!(vec.length(), 5);
+    assert_eq!(vec.capacity(), 5);
+
+    // The pop needs the spot right above the last element.
+    assert_eq!(vec.pop_back(0), Some(4));
+    assert_eq!(vec.capacity(), 5);
+    assert_eq!(vec.try_push_back(0, 6), Ok(()));
+    assert_eq!(vec.try_push_back(0, 7), Err(CapacityExceeded(7)));
+
+    vec.resize();
+    vec.reserve(0, 100);
+    assert_eq!(vec.capacity(), 5);
+    assert_eq!(vec.snapshot(0), vec![0, 1, 2, 3, 6]);
+}
+
+#[test]
+#[should_panic]
+fn push_back_panics_when_bounded_and_full() {
+    let vec = WaitFreeVector::with_growth_policy(1, 1, GrowthPolicy::Bounded(1));
+    vec.push_back(0, 0);
+    vec.push_back(0, 1);
+}
+
+#[test]
+fn threaded_pushes_stop_at_the_maximum() {
+    let num_threads = 4;
+    let max = 300;
+
+    let vec = Arc::new(WaitFreeVector::with_growth_policy(1, num_threads, GrowthPolicy::Bounded(max)));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            (0..max).filter(|j| vec_thread.try_push_back(i, common::value(i, *j)).is_ok()).count()
+        }));
+    }
+
+    let pushed: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(pushed, max);
+    assert_eq!(vec.length(), max);
+    assert_eq!(vec.snapshot(0).len(), max);
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::{epoch, WaitFreeVector};
+
+mod common;
+
+#[test]
+fn seq_iter_yields_values_in_order() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..100 {
+        vec.push_back(0, i);
+    }
+
+    let guard = &epoch::pin();
+    assert_eq!(vec.iter(0, guard).copied().collect::<Vec<_>>(), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_skips_popped_spots() {
+    let vec = WaitFreeVector::new(16, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+    for _ in 0..3 {
+        vec.pop_back(0);
+    }
+
+    let guard = &epoch::pin();
+    assert_eq!(vec.iter(0, guard).copied().collect::<Vec<_>>(), (0..7).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_follows_resize() {
+    let vec = WaitFreeVector::new(4, 1);
+
+    for i in 0..4 {
+        vec.push_back(0, i);
+    }
+
+    let guard = &epoch::pin();
+    let mut iter = vec.iter(0, guard);
+    assert_eq!(iter.next(), Some(&0));
+    assert_eq!(iter.next(), Some(&1));
+
+    // Two resizes while the iterator sits in the first generation.
+    for i in 4..20 {
+        vec.push_back(0, i);
+    }
+    assert!(vec.capacity() > 4);
+
+    assert_eq!(iter.copied().collect::<Vec<_>>(), (2..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn segmented_iter_crosses_buckets() {
+    let vec = WaitFreeVector::new_segmented(0, 1);
+
+    for i in 0..30 {
+        vec.push_back(0, i);
+    }
+
+    let guard = &epoch::pin();
+    assert_eq!(vec.iter(0, guard).copied().collect::<Vec<_>>(), (0..30).collect::<Vec<_>>());
+}
+
+#[test]
+fn threaded_iter_sees_only_pushed_values() {
+    let num_threads = 4;
+    let times = common::times(300);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    let pushed = common::pushed(num_threads, times);
+    let vec_thread = vec.clone();
+    let reader = thread::spawn(move || {
+        for _ in 0..100 {
+            let guard = &epoch::pin();
+            for value in vec_thread.iter(num_threads, guard) {
+                assert!(pushed.binary_search(value).is_ok(), "{} was never pushed", value);
+            }
+        }
+    });
+
+    common::join(pushers);
+    reader.join().unwrap();
+
+    let guard = &epoch::pin();
+    assert_eq!(vec.iter(0, guard).count(), num_threads * times);
+}
+
+// The pools of a fixed vector hand popped boxes out again, but not while an
+// iterator still has references into them.
+#[test]
+fn bounded_iter_keeps_its_values() {
+    let vec = WaitFreeVector::bounded(4, 2);
+
+    for i in 0..4 {
+        vec.push_back(0, i);
+    }
+
+    let guard = &epoch::pin();
+    let seen = vec.iter(1, guard).collect::<Vec<_>>();
+
+    for round in 1..=if cfg!(miri) { 5 } else { 100 } {
+        for _ in 0..4 {
+            vec.pop_back(0);
+        }
+        for i in 0..4 {
+            vec.push_back(0, round * 100 + i);
+        }
+    }
+
+    assert_eq!(seen, [&0, &1, &2, &3]);
+}
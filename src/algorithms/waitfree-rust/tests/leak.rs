@@ -0,0 +1,231 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Barrier};
+use std::sync::atomic::AtomicIsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::thread;
+use waitfree_rust::{epoch, WaitFreeVector};
+
+mod common;
+
+// Counts the allocations of the whole test binary that are still alive.
+// The binary does without the test harness, see Cargo.toml, and runs
+// everything from main, so no thread of the harness allocates while a
+// scenario is measured.
+struct Counting;
+
+static LIVE: AtomicIsize = AtomicIsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE.fetch_add(1, SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE.fetch_sub(1, SeqCst);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+// Runs `scenario` once to warm up and then ROUNDS times, draining the
+// epochs before and after, and reports how many allocations are left over.
+// What the epochs keep for themselves, a record per thread and the room in
+// its bag, is all there after the warm-up, so nothing may be left over,
+// and under Miri a single round shows a leak as well as twenty.
+const ROUNDS: isize = if cfg!(miri) { 1 } else { 20 };
+
+fn leaks(scenario: impl Fn()) -> isize {
+    scenario();
+    drain();
+
+    let baseline = LIVE.load(SeqCst);
+    for _ in 0..ROUNDS {
+        scenario();
+    }
+    drain();
+
+    LIVE.load(SeqCst) - baseline
+}
+
+// Every flush moves the epoch on a step while nobody else is pinned, and
+// three steps run whatever was deferred before them. What those defer in
+// turn takes another three.
+fn drain() {
+    for _ in 0..100 {
+        epoch::pin().flush();
+    }
+}
+
+// The epochs keep a record for as many threads as were ever pinned at once,
+// so the threaded scenarios only leave the count alone once that many have
+// been: this thread and NUM_THREADS others.
+fn pin_side_by_side() {
+    let barrier = Arc::new(Barrier::new(NUM_THREADS));
+    let _guard = epoch::pin();
+
+    let handles: Vec<_> = (0..NUM_THREADS).map(|_| {
+        let barrier = barrier.clone();
+        thread::spawn(move || {
+            let _guard = epoch::pin();
+            barrier.wait();
+        })
+    }).collect();
+
+    common::join(handles);
+}
+
+// Compares like the string in it, except that comparing TRIP panics and
+// leaves the operation that compared it half done.
+#[derive(Clone, Debug)]
+struct Tripwire(String);
+
+const TRIP: &str = "trip";
+
+impl PartialEq for Tripwire {
+    fn eq(&self, other: &Tripwire) -> bool {
+        assert!(self.0 != TRIP && other.0 != TRIP, "tripped");
+        self.0 == other.0
+    }
+}
+
+fn push_and_pop() {
+    let vec = WaitFreeVector::new(1, 1);
+    for i in 0..100 {
+        vec.push_back(0, i.to_string());
+    }
+    for _ in 0..40 {
+        vec.pop_back(0);
+    }
+    assert!(vec.cwrite(0, 0, "0".to_string(), "zero".to_string()));
+    assert_eq!(vec.snapshot(0).len(), 60);
+}
+
+fn shrink_after_pops() {
+    let vec = WaitFreeVector::new(64, 1);
+    for i in 0..20 {
+        vec.push_back(0, Box::new(i));
+    }
+    for _ in 0..10 {
+        vec.pop_back(0);
+    }
+    assert!(vec.shrink_to_fit(0));
+}
+
+fn drop_during_migration() {
+    let vec = WaitFreeVector::new(300, 1);
+    for i in 0..300 {
+        vec.push_back(0, vec![i]);
+    }
+    vec.resize();
+    assert_eq!(vec.generations(), 2);
+}
+
+fn segmented() {
+    let vec = WaitFreeVector::new_segmented(0, 1);
+    for i in 0..100 {
+        vec.push_back(0, i.to_string());
+    }
+    for _ in 0..50 {
+        vec.pop_back(0);
+    }
+}
+
+const NUM_THREADS: usize = 4;
+
+// Every thread pushes 100 values and pops every other one, so the vector
+// is dropped with half of them still in it.
+fn push_and_pop_on(vec: WaitFreeVector<String>) {
+    let vec = Arc::new(vec);
+
+    let handles: Vec<_> = (0..NUM_THREADS).map(|i| {
+        let vec_thread = vec.clone();
+        thread::spawn(move || {
+            for j in 0..100 {
+                vec_thread.push_back(i, common::value(i, j).to_string());
+                if j % 2 == 1 {
+                    vec_thread.pop_back(i);
+                }
+            }
+        })
+    }).collect();
+
+    common::join(handles);
+}
+
+fn threaded_push_and_pop() {
+    push_and_pop_on(WaitFreeVector::new(1, NUM_THREADS));
+}
+
+// A fixed vector keeps descriptors and value boxes in its pools, retired
+// ones among them, and frees them all when it is dropped.
+fn bounded() {
+    push_and_pop_on(WaitFreeVector::bounded(256, NUM_THREADS));
+}
+
+// Whoever loses a compare_exchange tries again with what it found, so
+// threads keep replacing each other's values and retiring the old ones.
+// One that loses LIMIT times in a row is announced, and its helpers write
+// through WriteDescrs.
+fn compare_exchange() {
+    let vec = Arc::new(WaitFreeVector::new(1, NUM_THREADS));
+    vec.push_back(0, 0.to_string());
+
+    let handles: Vec<_> = (0..NUM_THREADS).map(|tid| {
+        let vec = vec.clone();
+        thread::spawn(move || {
+            let mut seen = vec.at(tid, 0).unwrap();
+            for _ in 0..common::times(100) {
+                let next = (seen.parse::<usize>().unwrap() + 1).to_string();
+                match vec.compare_exchange(tid, 0, seen, next.clone()) {
+                    Ok(_) => seen = next,
+                    Err(found) => seen = found.unwrap(),
+                }
+            }
+        })
+    }).collect();
+
+    common::join(handles);
+}
+
+// More positions than a cas_multi takes before it announces itself, see
+// LIMIT in lib.rs. It trips over the last one, and the vector is dropped
+// with the cas_multi still in the announcement table and still holding
+// every other position with a MultiSlotDescr.
+const PENDING_POSITIONS: usize = 1100;
+
+fn pending_cas_multi() {
+    let vec = WaitFreeVector::new(1, 1);
+    for i in 0..PENDING_POSITIONS {
+        vec.push_back(0, Tripwire(i.to_string()));
+    }
+
+    let mut entries: Vec<_> = (0..PENDING_POSITIONS).map(|i| (i, Tripwire(i.to_string()), Tripwire(format!("new {}", i)))).collect();
+    entries[PENDING_POSITIONS - 1].1 = Tripwire(TRIP.to_string());
+
+    // Without a word, tripping is what it is here for.
+    panic::set_hook(Box::new(|_| ()));
+    let tripped = panic::catch_unwind(AssertUnwindSafe(|| vec.cas_multi(0, &entries)));
+    drop(panic::take_hook());
+    assert!(tripped.is_err());
+}
+
+fn main() {
+    pin_side_by_side();
+
+    for (name, scenario) in [
+        ("push_and_pop", push_and_pop as fn()),
+        ("shrink_after_pops", shrink_after_pops),
+        ("drop_during_migration", drop_during_migration),
+        ("segmented", segmented),
+        ("threaded_push_and_pop", threaded_push_and_pop),
+        ("bounded", bounded),
+        ("compare_exchange", compare_exchange),
+        ("pending_cas_multi", pending_cas_multi),
+    ] {
+        assert_eq!(leaks(scenario), 0, "{} left allocations behind", name);
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn seq_length_follows_push_and_pop() {
+    let vec = WaitFreeVector::new(2, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+        assert_eq!(vec.length(), i + 1);
+    }
+    for i in (0..10).rev() {
+        vec.pop_back(0);
+        assert_eq!(vec.length(), i);
+    }
+
+    assert_eq!(vec.pop_back(0), None);
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn threaded_length_agrees_with_at() {
+    let num_threads = 4;
+    let times = common::times(200);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    // Nothing is popped, so every element length() counts is readable and
+    // every element at() returns is already counted.
+    let vec_thread = vec.clone();
+    let reader = thread::spawn(move || {
+        for round in 0..if cfg!(miri) { 50 } else { 5000 } {
+            let len = vec_thread.length();
+            if len > 0 {
+                assert!(vec_thread.at(num_threads, len - 1).is_some(), "length {} counts an unreadable element", len);
+            }
+
+            let pos = round % (num_threads * times);
+            if vec_thread.at(num_threads, pos).is_some() {
+                assert!(vec_thread.length() > pos, "element {} readable but not counted", pos);
+            }
+        }
+    });
+
+    common::join(pushers);
+    reader.join().unwrap();
+
+    assert_eq!(vec.length(), num_threads * times);
+}
+
+#[test]
+fn threaded_push_and_pop_keep_length_exact() {
+    let num_threads = 4;
+    let times = common::times(100);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut popped = 0;
+            for j in 0..times {
+                vec_thread.push_back(i, common::value(i, j));
+                if j % 2 == 0 && vec_thread.pop_back(i).is_some() {
+                    popped += 1;
+                }
+            }
+            popped
+        }));
+    }
+
+    let popped: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+
+    assert_eq!(vec.length(), num_threads * times - popped);
+    assert_eq!(vec.snapshot(0).len(), vec.length());
+}
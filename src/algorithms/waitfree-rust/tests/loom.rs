@@ -0,0 +1,178 @@
+// Runs the vector on loom's atomics, which explores the interleavings and
+// the weak-memory outcomes the orderings in lib.rs allow. The epochs in
+// epoch.rs pin on loom's fences as well:
+//
+//     RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//
+// LOOM_MAX_PREEMPTIONS bounds the search: 2 takes seconds, 3 two minutes.
+// Loom runs every SeqCst access as AcqRel, so the shrink seals, the one
+// place that depends on SeqCst, are not checked here.
+#![cfg(loom)]
+
+use std::sync::atomic::Ordering::Relaxed;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+use waitfree_rust::WaitFreeVector;
+
+// Runs `first` and `second` against `vec` on two threads, as tids 0 and 1.
+fn race<A, B>(vec: Arc<WaitFreeVector>, first: A, second: B)
+where
+    A: FnOnce(&WaitFreeVector) + Send + 'static,
+    B: FnOnce(&WaitFreeVector) + Send + 'static,
+{
+    let other = vec.clone();
+    let handle = thread::spawn(move || second(&other));
+    first(&vec);
+    handle.join().unwrap();
+}
+
+#[test]
+fn concurrent_pushes() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(2, 2));
+        race(vec.clone(), |vec| vec.push_back(0, 10), |vec| vec.push_back(1, 20));
+
+        assert_eq!(vec.length(), 2);
+        let mut values = vec.snapshot(0);
+        values.sort();
+        assert_eq!(values, vec![10, 20]);
+    });
+}
+
+#[test]
+fn push_against_pop() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(2, 2));
+        vec.push_back(0, 10);
+
+        let popped = Arc::new(Mutex::new(None));
+        let out = popped.clone();
+        race(vec.clone(), |vec| vec.push_back(0, 20), move |vec| *out.lock().unwrap() = vec.pop_back(1));
+
+        // The pop took either value, the other one is still there.
+        let popped = popped.lock().unwrap().expect("a value was there all along");
+        let left = vec.snapshot(0);
+        assert_eq!(left.len(), 1);
+        assert_eq!(vec.length(), 1);
+        assert!(matches!((popped, left[0]), (10, 20) | (20, 10)), "popped {} left {}", popped, left[0]);
+    });
+}
+
+#[test]
+fn at_sees_a_whole_push() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(2, 2));
+        race(vec.clone(), |vec| vec.push_back(0, 10), |vec| {
+            // The value is only readable once the push has counted it.
+            if let Some(value) = vec.at(1, 0) {
+                assert_eq!(value, 10);
+                assert_eq!(vec.length(), 1);
+            }
+        });
+
+        assert_eq!(vec.at(0, 0), Some(10));
+    });
+}
+
+#[test]
+fn one_cwrite_wins() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(1, 2));
+        vec.push_back(0, 1);
+
+        let won = Arc::new(AtomicUsize::new(0));
+        let (a, b) = (won.clone(), won.clone());
+        race(
+            vec.clone(),
+            move |vec| if vec.cwrite(0, 0, 1, 2) { a.fetch_add(1, Relaxed); },
+            move |vec| if vec.cwrite(1, 0, 1, 3) { b.fetch_add(1, Relaxed); },
+        );
+
+        assert_eq!(won.load(Relaxed), 1);
+        assert!(matches!(vec.at(0, 0), Some(2) | Some(3)));
+    });
+}
+
+#[test]
+fn pushes_racing_a_resize() {
+    loom::model(|| {
+        // Both pushes run out of room, so both try to install the next
+        // generation and help migrate the value already there.
+        let vec = Arc::new(WaitFreeVector::new(1, 2));
+        vec.push_back(0, 10);
+
+        race(vec.clone(), |vec| vec.push_back(0, 20), |vec| vec.resize());
+
+        assert_eq!(vec.snapshot(0), vec![10, 20]);
+        assert_eq!(vec.generations(), 1);
+    });
+}
+
+#[test]
+fn overlapping_cas_multis() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(2, 2));
+        vec.push_back(0, 1);
+        vec.push_back(0, 2);
+
+        // Both want both positions, taken in opposite order. One of them
+        // gets both, the other changes neither.
+        let won = Arc::new(AtomicUsize::new(0));
+        let (a, b) = (won.clone(), won.clone());
+        race(
+            vec.clone(),
+            move |vec| if vec.cas_multi(0, &[(0, 1, 10), (1, 2, 20)]) { a.fetch_add(1, Relaxed); },
+            move |vec| if vec.cas_multi(1, &[(1, 2, 40), (0, 1, 30)]) { b.fetch_add(1, Relaxed); },
+        );
+
+        assert_eq!(won.load(Relaxed), 1);
+        assert!(matches!(&vec.snapshot(0)[..], [10, 20] | [30, 40]));
+    });
+}
+
+#[test]
+fn cas_multi_sees_a_counted_push() {
+    loom::model(|| {
+        let vec = Arc::new(WaitFreeVector::new(2, 2));
+        vec.push_back(0, 0);
+
+        // The push may be counted while its descriptor is still in the spot.
+        race(vec.clone(), |vec| vec.push_back(0, 1), |vec| {
+            let n = vec.length();
+            assert!(vec.cas_multi(1, &[(n - 1, n - 1, n - 1)]));
+        });
+    });
+}
+
+// A bounded vector hands its descriptors out again once its own epochs say
+// nobody can see them any more. Under loom its pools hold two of each, so
+// the third push of the first thread reuses the descriptor of its first,
+// which a reader of spot 0 may still be helping.
+#[test]
+fn bounded_reuses_behind_a_helper() {
+    // Pinning twice per operation takes more steps than loom allows by default.
+    let mut builder = loom::model::Builder::new();
+    builder.max_branches = 10_000;
+
+    builder.check(|| {
+        let vec = Arc::new(WaitFreeVector::bounded(2, 2));
+
+        let read = Arc::new(Mutex::new(None));
+        let out = read.clone();
+        race(
+            vec.clone(),
+            |vec| {
+                vec.push_back(0, 10);
+                vec.push_back(0, 20);
+                assert_eq!(vec.pop_back(0), Some(20));
+                vec.push_back(0, 30);
+            },
+            move |vec| *out.lock().unwrap() = vec.at(1, 0),
+        );
+
+        assert!(matches!(*read.lock().unwrap(), None | Some(10)));
+        assert_eq!(vec.snapshot(0), vec![10, 30]);
+        assert_eq!(vec.length(), 2);
+    });
+}
@@ -0,0 +1,65 @@
+use std::sync::atomic::Ordering::SeqCst;
+use waitfree_rust::{epoch, AtomicMarkablePtr, MarkedPtr};
+
+#[test]
+fn free_bits_follow_alignment() {
+    assert_eq!(AtomicMarkablePtr::<u16>::MARK_BITS, 1);
+    assert_eq!(AtomicMarkablePtr::<u32>::MARK_BITS, 2);
+    assert_eq!(AtomicMarkablePtr::<u64>::MARK_MASK, 0b111);
+}
+
+#[test]
+fn marks_leave_the_pointer_alone() {
+    let guard = &epoch::pin();
+    let atomic = AtomicMarkablePtr::new(7u64);
+
+    let plain = atomic.load(SeqCst, guard);
+    assert_eq!(plain.mark(), 0);
+    assert_eq!(unsafe { *plain.deref() }, 7);
+
+    assert_eq!(atomic.mark(0b101, SeqCst, guard), plain);
+    let marked = atomic.load(SeqCst, guard);
+    assert_eq!(marked.mark(), 0b101);
+    assert_eq!(marked.as_raw(), plain.as_raw());
+    assert_eq!(unsafe { *marked.deref() }, 7);
+
+    assert_eq!(atomic.unmark(0b100, SeqCst, guard), marked);
+    assert_eq!(atomic.load(SeqCst, guard), plain.with_mark(0b001));
+
+    drop(unsafe { atomic.load(SeqCst, guard).into_box() });
+}
+
+#[test]
+fn compare_exchange_looks_at_the_mark() {
+    let guard = &epoch::pin();
+    let atomic = AtomicMarkablePtr::<u64>::from(MarkedPtr::null().with_mark(1));
+
+    // Right pointer, wrong mark.
+    assert_eq!(atomic.compare_exchange(MarkedPtr::null(), MarkedPtr::null().with_mark(2), SeqCst, SeqCst, guard),
+               Err(MarkedPtr::null().with_mark(1)));
+
+    let new = MarkedPtr::from_box(Box::new(3u64)).with_mark(2);
+    assert!(atomic.compare_exchange(MarkedPtr::null().with_mark(1), new, SeqCst, SeqCst, guard).is_ok());
+
+    let loaded = atomic.load(SeqCst, guard);
+    assert!(!loaded.is_null());
+    assert_eq!(loaded.mark(), 2);
+
+    let old = atomic.swap(MarkedPtr::null(), SeqCst, guard);
+    assert_eq!(old, new);
+    assert!(atomic.load(SeqCst, guard).is_null());
+    unsafe { old.defer_destroy(guard) };
+}
+
+#[test]
+fn casts_keep_the_mark() {
+    let guard = &epoch::pin();
+    let word: MarkedPtr<i64> = MarkedPtr::from_box(Box::new(9u64)).with_mark(3).cast();
+
+    assert_eq!(word.mark(), 3);
+
+    let back = word.cast::<u64>();
+    assert_eq!(back.mark(), 3);
+    assert_eq!(unsafe { *back.deref() }, 9);
+    unsafe { back.defer_destroy(guard) };
+}
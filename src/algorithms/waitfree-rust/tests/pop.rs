@@ -0,0 +1,132 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn pop_takes_the_last_element() {
+    let vec = WaitFreeVector::new(4, 1);
+
+    for i in 0..3 {
+        vec.push_back(0, i);
+    }
+
+    assert_eq!(vec.pop_back(0), Some(2));
+    assert_eq!(vec.at(0, 2), None);
+    assert_eq!(vec.at(0, 1), Some(1));
+    assert_eq!(vec.length(), 2);
+
+    // The spot freed by the pop is reused by the next push.
+    vec.push_back(0, 5);
+    assert_eq!(vec.at(0, 2), Some(5));
+    assert_eq!(vec.pop_back(0), Some(5));
+}
+
+#[test]
+fn pop_after_resize() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..20 {
+        vec.push_back(0, i);
+    }
+    assert!(vec.capacity() >= 20);
+
+    for i in (0..20).rev() {
+        assert_eq!(vec.pop_back(0), Some(i));
+    }
+    assert_eq!(vec.pop_back(0), None);
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn pop_on_empty_segmented() {
+    let vec = WaitFreeVector::new_segmented(0, 1);
+
+    assert_eq!(vec.pop_back(0), None);
+    vec.push_back(0, 1);
+    assert_eq!(vec.pop_back(0), Some(1));
+    assert_eq!(vec.pop_back(0), None);
+}
+
+#[test]
+fn threaded_pops_on_empty_vector() {
+    let num_threads = 4;
+
+    let vec = Arc::new(WaitFreeVector::<usize>::new(1, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            for _ in 0..200 {
+                assert_eq!(vec_thread.pop_back(i), None);
+            }
+        }));
+    }
+
+    for h in handles {
+        h.join().unwrap();
+    }
+
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn threaded_pops_drain_the_vector_exactly_once() {
+    let num_threads = 4;
+    let total = if cfg!(miri) { 40 } else { 400 };
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    for i in 0..total {
+        vec.push_back(0, i);
+    }
+
+    let mut handles = Vec::new();
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut popped = Vec::new();
+            // More pops than elements, so some of them find it empty.
+            for _ in 0..total / num_threads + 20 {
+                if let Some(v) = vec_thread.pop_back(i) {
+                    popped.push(v);
+                }
+            }
+            popped
+        }));
+    }
+
+    let mut popped: Vec<usize> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    popped.sort_unstable();
+
+    assert_eq!(popped, (0..total).collect::<Vec<_>>());
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn threaded_push_and_pop_lose_nothing() {
+    let num_threads = 4;
+    let times = common::times(200);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut popped = Vec::new();
+            for j in 0..times {
+                vec_thread.push_back(i, common::value(i, j));
+                if j % 2 == 1 {
+                    popped.extend(vec_thread.pop_back(i));
+                }
+            }
+            popped
+        }));
+    }
+
+    let mut seen: Vec<usize> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    seen.extend(vec.snapshot(0));
+    common::assert_all_pushed(seen, num_threads, times);
+}
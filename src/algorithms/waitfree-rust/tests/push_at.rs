@@ -0,0 +1,87 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn only_appends_at_the_end() {
+    for vec in [WaitFreeVector::new(1, 1), WaitFreeVector::new_segmented(1, 1), WaitFreeVector::bounded(2, 1)] {
+        assert_eq!(vec.try_push_at(0, 1, 10), Err(10));
+        assert_eq!(vec.try_push_at(0, 0, 10), Ok(()));
+        assert_eq!(vec.try_push_at(0, 0, 20), Err(20));
+        assert_eq!(vec.try_push_at(0, 2, 20), Err(20));
+        assert_eq!(vec.try_push_at(0, 1, 20), Ok(()));
+        assert_eq!(vec.snapshot(0), vec![10, 20]);
+
+        // Past the end of a bounded vector, or after a pop made it shorter.
+        assert_eq!(vec.try_push_at(0, 2, 30), if vec.capacity() == 2 { Err(30) } else { Ok(()) });
+        while vec.length() > 1 {
+            vec.pop_back(0);
+        }
+        assert_eq!(vec.try_push_at(0, 2, 40), Err(40));
+        assert_eq!(vec.try_push_at(0, 1, 40), Ok(()));
+        assert_eq!(vec.snapshot(0), vec![10, 40]);
+    }
+}
+
+// Writers of a shared log each append at the length they last saw. Of those
+// that try the same index exactly one gets it, the rest learn the log moved
+// on and try again one further.
+#[test]
+fn one_writer_per_index() {
+    let num_threads = 4;
+    let times = common::times(1000);
+
+    let vec = Arc::new(WaitFreeVector::new(8, num_threads));
+    let mut handles = Vec::new();
+
+    for tid in 0..num_threads {
+        let vec = vec.clone();
+        handles.push(thread::spawn(move || {
+            let mut index = 0;
+            let mut appended = Vec::new();
+
+            for i in 0..times {
+                loop {
+                    let entry = tid * times + i;
+                    match vec.try_push_at(tid, index, entry) {
+                        Ok(()) => {
+                            appended.push(index);
+                            break;
+                        },
+                        Err(back) => {
+                            assert_eq!(back, entry);
+                            index += 1;
+                        },
+                    }
+                }
+            }
+
+            appended
+        }));
+    }
+
+    let mut indices: Vec<usize> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    indices.sort();
+
+    assert_eq!(indices, (0..num_threads * times).collect::<Vec<_>>());
+    assert_eq!(vec.length(), num_threads * times);
+}
+
+// Only the length decides, so an index far past it neither grows the
+// storage nor allocates a spot for it.
+#[test]
+fn a_far_index_touches_nothing() {
+    for vec in [WaitFreeVector::new(1, 1), WaitFreeVector::new_segmented(1, 1)] {
+        vec.push_back(0, 1);
+        let (capacity, generations) = (vec.capacity(), vec.generations());
+
+        assert_eq!(vec.try_push_at(0, 50_000_000, 2), Err(2));
+        assert_eq!(vec.try_push_at(0, 1 << 40, 2), Err(2));
+        assert_eq!(vec.try_push_at(0, usize::MAX, 2), Err(2));
+        assert_eq!(vec.capacity(), capacity);
+        assert_eq!(vec.generations(), generations);
+        assert_eq!(vec.snapshot(0), vec![1]);
+    }
+}
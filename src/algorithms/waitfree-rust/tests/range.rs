@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::{RangeSlot, WaitFreeVector};
+use RangeSlot::{BeyondSize, Empty, Read};
+
+mod common;
+
+#[test]
+fn read_window_in_the_middle() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..100 {
+        vec.push_back(0, i);
+    }
+
+    let mut out = [0; 10];
+    assert_eq!(vec.read_range(0, 40..50, &mut out), vec![Read; 10]);
+    assert_eq!(out, [40, 41, 42, 43, 44, 45, 46, 47, 48, 49]);
+}
+
+#[test]
+fn positions_past_the_length_are_reported() {
+    let vec = WaitFreeVector::new(8, 1);
+
+    for i in 0..6 {
+        vec.push_back(0, i.to_string());
+    }
+    vec.pop_back(0);
+
+    let mut out = vec!["untouched".to_string(); 6];
+    let slots = vec.read_range(0, 3..9, &mut out);
+
+    // 5 was popped, 8 is past the capacity as well.
+    assert_eq!(slots, vec![Read, Read, BeyondSize, BeyondSize, BeyondSize, BeyondSize]);
+    assert_eq!(out[..2], ["3".to_string(), "4".to_string()]);
+    assert!(out[2..].iter().all(|s| s == "untouched"));
+    assert_eq!(vec.capacity(), 8);
+}
+
+#[test]
+fn empty_range_reads_nothing() {
+    let vec = WaitFreeVector::<usize>::new(4, 1);
+    assert_eq!(vec.read_range(0, 2..2, &mut []), Vec::<RangeSlot>::new());
+    assert_eq!(vec.snapshot_range(0, 2..2, &mut []), Vec::<RangeSlot>::new());
+}
+
+#[test]
+#[should_panic]
+fn output_has_to_match_the_range() {
+    let vec = WaitFreeVector::<usize>::new(4, 1);
+    vec.read_range(0, 0..4, &mut [0; 3]);
+}
+
+#[test]
+fn segmented_snapshot_range() {
+    let vec = WaitFreeVector::new_segmented(0, 1);
+
+    for i in 0..50 {
+        vec.push_back(0, i);
+    }
+
+    let mut out = [0; 5];
+    assert_eq!(vec.snapshot_range(0, 47..52, &mut out), vec![Read, Read, Read, BeyondSize, BeyondSize]);
+    assert_eq!(out[..3], [47, 48, 49]);
+}
+
+#[test]
+fn threaded_snapshot_ranges_are_prefixes() {
+    let num_threads = 4;
+    let times = common::times(300);
+    let window = num_threads * times;
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    let vec_thread = vec.clone();
+    let reader = thread::spawn(move || {
+        let mut out = vec![0; window];
+        for _ in 0..50 {
+            let slots = vec_thread.snapshot_range(num_threads, 0..window, &mut out);
+
+            // Nothing is ever popped, so an atomic read sees some prefix of
+            // the vector and every value of a thread up to its latest push.
+            let read = slots.iter().take_while(|s| **s == Read).count();
+            assert!(slots[read..].iter().all(|s| *s == BeyondSize));
+            assert!(!slots.contains(&Empty));
+
+            common::assert_prefixes(&out[..read], num_threads);
+        }
+    });
+
+    common::join(pushers);
+    reader.join().unwrap();
+
+    let mut out = vec![0; window];
+    assert_eq!(vec.snapshot_range(0, 0..window, &mut out), vec![Read; window]);
+}
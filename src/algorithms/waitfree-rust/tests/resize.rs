@@ -0,0 +1,192 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn resize_retires_old_generation() {
+    let vec = WaitFreeVector::new(1, 1);
+    assert_eq!(vec.generations(), 1);
+
+    vec.push_back(0, 10);
+    vec.resize();
+
+    assert_eq!(vec.capacity(), 3);
+    assert_eq!(vec.generations(), 1);
+    assert_eq!(vec.at(0, 0), Some(10));
+}
+
+#[test]
+fn repeated_resizes_keep_chain_bounded() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..1000 {
+        vec.push_back(0, i);
+        assert!(vec.generations() <= 2);
+    }
+
+    // 1 -> 3 -> 7 -> ... -> 1023
+    assert_eq!(vec.capacity(), 1023);
+    assert_eq!(vec.length(), 1000);
+
+    for i in 0..1000 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+}
+
+#[test]
+fn resize_from_empty_storage() {
+    let vec = WaitFreeVector::new(0, 1);
+
+    vec.push_back(0, 1);
+    vec.push_back(0, 2);
+
+    assert_eq!(vec.generations(), 1);
+    assert_eq!(vec.at(0, 0), Some(1));
+    assert_eq!(vec.at(0, 1), Some(2));
+}
+
+#[test]
+fn threaded_push_across_resizes_loses_nothing() {
+    let num_threads = 4;
+    let times = common::times(200);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    common::join(pushers);
+
+    assert_eq!(vec.length(), num_threads * times);
+    assert_eq!(vec.generations(), 1);
+
+    let seen = (0..num_threads * times).map(|i| vec.at(0, i).unwrap()).collect();
+    common::assert_all_pushed(seen, num_threads, times);
+}
+
+#[test]
+fn threaded_resize_calls_while_pushing() {
+    let num_threads = 4;
+    let times = common::times(100);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            for j in 0..times {
+                if i == 0 && j % 10 == 0 {
+                    vec_thread.resize();
+                }
+                vec_thread.push_back(i, j);
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(vec.length(), num_threads * times);
+    for i in 0..num_threads * times {
+        assert!(vec.at(0, i).is_some());
+    }
+}
+
+#[test]
+fn resize_leaves_migration_to_later_operations() {
+    let vec = WaitFreeVector::new(2000, 1);
+    for i in 0..2000 {
+        vec.push_back(0, i);
+    }
+
+    // The resizing thread only copies its own chunk.
+    vec.resize();
+    assert_eq!(vec.generations(), 2);
+
+    // Every lookup copies another chunk until nothing is left.
+    let mut lookups = 0;
+    while vec.generations() == 2 {
+        assert_eq!(vec.at(0, 1999), Some(1999));
+        lookups += 1;
+        assert!(lookups < 2000 / 256 + 1);
+    }
+
+    for i in 0..2000 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+}
+
+#[test]
+fn back_to_back_resizes_finish_the_pending_migration() {
+    let vec = WaitFreeVector::new(2000, 1);
+    for i in 0..2000 {
+        vec.push_back(0, i);
+    }
+
+    vec.resize();
+    vec.resize();
+
+    assert_eq!(vec.capacity(), 2 * (2 * 2000 + 1) + 1);
+    assert_eq!(vec.generations(), 2);
+
+    for i in 0..2000 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+    assert_eq!(vec.generations(), 1);
+}
+
+#[test]
+fn threaded_readers_share_the_migration() {
+    let num_threads = 4;
+    let len = if cfg!(miri) { 600 } else { 5000 };
+
+    let vec = Arc::new(WaitFreeVector::new(len, num_threads));
+    for i in 0..len {
+        vec.push_back(0, i);
+    }
+    vec.resize();
+
+    let mut handles = Vec::new();
+    for i in 0..num_threads {
+        let vec_thread = vec.clone();
+        handles.push(thread::spawn(move || {
+            for j in (0..len).rev() {
+                assert_eq!(vec_thread.at(i, j), Some(j));
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(vec.generations(), 1);
+    assert_eq!(vec.length(), len);
+}
+
+#[test]
+fn threaded_pushes_racing_shrinks_lose_nothing() {
+    let num_threads = 4;
+    let times = common::times(500);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    // Growth and shrinks both replace the current generation, and whichever
+    // is announced first on it wins while the other helps install it.
+    let vec_thread = vec.clone();
+    let shrinker = thread::spawn(move || {
+        for _ in 0..200 {
+            vec_thread.shrink_to_fit(num_threads);
+        }
+    });
+
+    common::join(pushers);
+    shrinker.join().unwrap();
+
+    let values = vec.snapshot(0);
+    common::assert_all_pushed(values, num_threads, times);
+    assert!(vec.generations() <= 2);
+}
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn preallocates_whole_buckets() {
+    assert_eq!(WaitFreeVector::<usize>::new_segmented(0, 1).capacity(), 0);
+    assert_eq!(WaitFreeVector::<usize>::new_segmented(8, 1).capacity(), 8);
+    // 20 spots need bucket 0 (8) and bucket 1 (16)
+    assert_eq!(WaitFreeVector::<usize>::new_segmented(20, 1).capacity(), 24);
+}
+
+#[test]
+fn seq_push_at_across_buckets() {
+    let vec = WaitFreeVector::new_segmented(1, 1);
+
+    for i in 0..100 {
+        vec.push_back(0, i * 10);
+    }
+
+    assert_eq!(vec.length(), 100);
+    assert!(vec.capacity() >= 100);
+
+    for i in 0..100 {
+        assert_eq!(vec.at(0, i), Some(i * 10));
+    }
+    assert_eq!(vec.at(0, 100), None);
+}
+
+#[test]
+fn growing_adds_a_bucket_without_touching_old_ones() {
+    let vec = WaitFreeVector::new_segmented(8, 1);
+
+    for i in 0..8 {
+        vec.push_back(0, i);
+    }
+    assert_eq!(vec.capacity(), 8);
+
+    vec.push_back(0, 8);
+    assert_eq!(vec.capacity(), 24);
+
+    for i in 0..9 {
+        assert_eq!(vec.at(0, i), Some(i));
+    }
+}
+
+#[test]
+fn seq_pop_and_cwrite() {
+    let vec = WaitFreeVector::new_segmented(1, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+
+    assert!(vec.cwrite(0, 9, 9, 90));
+    assert!(!vec.cwrite(0, 9, 9, 91));
+    assert_eq!(vec.at(0, 9), Some(90));
+
+    assert_eq!(vec.pop_back(0), Some(90));
+    assert_eq!(vec.pop_back(0), Some(8));
+    assert_eq!(vec.length(), 8);
+}
+
+#[test]
+fn threaded_push_grows_buckets() {
+    let num_threads = 4;
+    let times = common::times(50);
+
+    let vec = Arc::new(WaitFreeVector::new_segmented(1, num_threads));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    common::join(pushers);
+
+    assert_eq!(vec.length(), num_threads * times);
+
+    let seen = (0..num_threads * times).map(|i| vec.at(0, i).unwrap()).collect();
+    common::assert_all_pushed(seen, num_threads, times);
+}
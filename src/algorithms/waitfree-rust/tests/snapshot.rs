@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn snapshot_of_empty_vector() {
+    let vec = WaitFreeVector::<usize>::new(4, 1);
+    assert_eq!(vec.snapshot(0), Vec::<usize>::new());
+}
+
+#[test]
+fn seq_snapshot_matches_pushes() {
+    let vec = WaitFreeVector::new(1, 1);
+
+    for i in 0..100 {
+        vec.push_back(0, i);
+    }
+
+    assert_eq!(vec.snapshot(0), (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn snapshot_after_pops() {
+    let vec = WaitFreeVector::new(16, 1);
+
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+    for _ in 0..4 {
+        vec.pop_back(0);
+    }
+
+    assert_eq!(vec.snapshot(0), (0..6).collect::<Vec<_>>());
+}
+
+#[test]
+fn segmented_snapshot_matches_pushes() {
+    let vec = WaitFreeVector::new_segmented(0, 1);
+
+    for i in 0..50 {
+        vec.push_back(0, i);
+    }
+
+    assert_eq!(vec.snapshot(0), (0..50).collect::<Vec<_>>());
+}
+
+#[test]
+fn threaded_snapshots_are_consistent() {
+    let num_threads = 4;
+    let times = common::times(300);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    let vec_thread = vec.clone();
+    let reader = thread::spawn(move || {
+        let mut last = 0;
+        for _ in 0..50 {
+            let snapshot = vec_thread.snapshot(num_threads);
+            assert!(snapshot.len() >= last);
+            common::assert_prefixes(&snapshot, num_threads);
+            last = snapshot.len();
+        }
+    });
+
+    common::join(pushers);
+    reader.join().unwrap();
+
+    let snapshot = vec.snapshot(0);
+    assert_eq!(snapshot.len(), num_threads * times);
+    common::assert_prefixes(&snapshot, num_threads);
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn stale_version_loses_after_aba() {
+    let vec = WaitFreeVector::new(4, 1);
+    vec.push_back(0, 1);
+
+    let (value, version) = vec.at_versioned(0, 0).unwrap();
+    assert_eq!(value, 1);
+
+    // Back to the value we read, but not to the version.
+    assert!(vec.cwrite(0, 0, 1, 2));
+    assert!(vec.cwrite(0, 0, 2, 1));
+    assert!(!vec.cwrite_versioned(0, 0, version, 3));
+    assert_eq!(vec.at(0, 0), Some(1));
+
+    let (_, fresh) = vec.at_versioned(0, 0).unwrap();
+    assert_ne!(fresh, version);
+    assert!(vec.cwrite_versioned(0, 0, fresh, 3));
+    assert!(!vec.cwrite_versioned(0, 0, fresh, 4));
+    assert_eq!(vec.at(0, 0), Some(3));
+}
+
+#[test]
+fn pop_and_push_change_the_version() {
+    let vec = WaitFreeVector::new(4, 1);
+    vec.push_back(0, 7);
+    let (_, before) = vec.at_versioned(0, 0).unwrap();
+
+    assert_eq!(vec.pop_back(0), Some(7));
+    assert_eq!(vec.at_versioned(0, 0), None);
+    assert!(!vec.cwrite_versioned(0, 0, before, 8));
+
+    vec.push_back(0, 7);
+    let (_, after) = vec.at_versioned(0, 0).unwrap();
+    assert_ne!(before, after);
+    assert!(!vec.cwrite_versioned(0, 0, before, 8));
+}
+
+#[test]
+fn resizes_keep_versions() {
+    for vec in [WaitFreeVector::new(2, 1), WaitFreeVector::new_segmented(2, 1)] {
+        for i in 0..2 {
+            vec.push_back(0, i);
+        }
+        let versions: Vec<u64> = (0..2).map(|i| vec.at_versioned(0, i).unwrap().1).collect();
+
+        for i in 2..100 {
+            vec.push_back(0, i);
+        }
+        vec.reserve(0, 1000);
+        for _ in 2..100 {
+            vec.pop_back(0);
+        }
+        vec.shrink_to_fit(0);
+
+        for (i, &version) in versions.iter().enumerate() {
+            assert_eq!(vec.at_versioned(0, i), Some((i, version)));
+            assert!(vec.cwrite_versioned(0, i, version, i + 10));
+        }
+        assert_eq!(vec.snapshot(0), vec![10, 11]);
+    }
+}
+
+#[test]
+fn optimistic_increments_are_not_lost() {
+    let num_threads = 4;
+    let times = common::times(1000);
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads));
+    vec.push_back(0, 0);
+
+    let mut handles = Vec::new();
+    for tid in 0..num_threads {
+        let vec = vec.clone();
+        handles.push(thread::spawn(move || {
+            for _ in 0..times {
+                loop {
+                    let (value, version) = vec.at_versioned(tid, 0).unwrap();
+                    if vec.cwrite_versioned(tid, 0, version, value + 1) {
+                        break;
+                    }
+                }
+            }
+        }));
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    assert_eq!(vec.at(0, 0), Some(num_threads * times));
+}
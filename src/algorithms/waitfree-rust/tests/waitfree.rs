@@ -0,0 +1,242 @@
+use std::sync::Arc;
+use std::thread;
+use waitfree_rust::WaitFreeVector;
+
+mod common;
+
+#[test]
+fn insert_vals_seq(){
+    let vec = WaitFreeVector::new(3, 1);
+    vec.push_back(0, 10);
+    vec.push_back(0, 11);
+    vec.push_back(0, 12);
+}
+#[test]
+fn len_seq(){
+    let vec = WaitFreeVector::new(3, 1);
+    vec.push_back(0, 10);
+    vec.push_back(0, 11);
+    assert_eq!(vec.length(), 2);
+}
+
+#[test]
+fn seq_at(){
+    let vec = WaitFreeVector::new(2, 1);
+    vec.push_back(0, 10);
+    vec.push_back(0, 20);
+    vec.at(0, 0);
+
+    assert_eq!(vec.at(0, 0), Some(10));
+    assert_eq!(vec.at(0, 1), Some(20));
+}
+
+#[test]
+fn seq_resize_at() {
+    // There should be 2 resizes happening here.
+    let vec = WaitFreeVector::new(1, 1);
+
+    vec.push_back(0, 10);
+    vec.push_back(0, 20);
+    vec.push_back(0, 30);
+    vec.push_back(0, 40);
+
+    assert_eq!(vec.at(0, 0), Some(10));
+    assert_eq!(vec.at(0, 1), Some(20));
+    assert_eq!(vec.at(0, 2), Some(30));
+    assert_eq!(vec.at(0, 3), Some(40));
+
+    assert_eq!(vec.length(), 4)
+}
+
+#[test]
+fn threaded_insert_len(){
+    let capacity = 100;
+    let num_threads = 8;
+    let times = 12;
+    assert!(num_threads*times < capacity);
+
+    let vec = Arc::new(WaitFreeVector::new(100, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+
+        let vec_thread = vec.clone();
+        handles.push(
+            thread::spawn(
+                move || {
+                    for _ in 0..times {
+                        vec_thread.push_back(i, i*i);
+                    }
+                }
+            )
+        );
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(vec.length(), num_threads * times);
+}
+
+#[test]
+fn threaded_insert_and_check_all_are_some(){
+    let capacity = 5;
+    let num_threads = 4;
+    let times = 3;
+    // assert!(num_threads*times < capacity);
+
+    let vec = Arc::new(WaitFreeVector::new(capacity, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+
+        let vec_thread = vec.clone();
+        handles.push(
+            thread::spawn(
+                move || {
+                    for _ in 0..times {
+                        vec_thread.push_back(i, i*i);
+                    }
+                }
+            )
+        );
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(vec.length(), num_threads * times);
+
+    for i in 0..num_threads * times {
+        assert!(vec.at(0, i).is_some());
+    }
+}
+
+#[test]
+fn threaded_resize() {
+    let capacity = 1;
+    let num_threads = 4;
+    let times = 5;
+    assert!(num_threads*times > capacity);
+    
+    let vec = Arc::new(WaitFreeVector::new(100, num_threads));
+    let mut handles = Vec::new();
+
+    for i in 0..num_threads {
+
+        let vec_thread = vec.clone();
+        handles.push(
+            thread::spawn(
+                move || {
+                    for _ in 0..times {
+                        vec_thread.push_back(i, i*i);
+                    }
+                }
+            )
+        );
+    }
+
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    println!("{}", vec.length());
+    assert_eq!(vec.length(), num_threads * times);
+}
+
+#[test]
+fn pop_back() {
+    let vec = WaitFreeVector::new(2, 1);
+    vec.push_back(0, 10);
+    vec.push_back(0, 20);
+    vec.at(0, 0);
+
+    assert_eq!(vec.at(0, 0), Some(10));
+    assert_eq!(vec.at(0, 1), Some(20));
+
+    assert_eq!(vec.pop_back(0), Some(20));
+    assert_eq!(vec.pop_back(0), Some(10));
+    assert_eq!(vec.pop_back(0), None);
+
+    assert_eq!(vec.length(), 0);
+}
+
+#[test]
+fn at_past_the_end() {
+    let vec = WaitFreeVector::new(4, 1);
+    vec.push_back(0, 10);
+
+    assert_eq!(vec.at(0, 1), None);
+    assert_eq!(vec.at(0, 100), None);
+    assert_eq!(vec.capacity(), 4);
+
+    vec.pop_back(0);
+    assert_eq!(vec.at(0, 0), None);
+}
+
+// Spots between the length and the capacity exist but hold nothing, so
+// reading them does not even look them up. Looking them up would copy a
+// chunk of the pending migration every time.
+#[test]
+fn at_between_length_and_capacity() {
+    let vec = WaitFreeVector::new(1000, 1);
+    for i in 0..10 {
+        vec.push_back(0, i);
+    }
+
+    vec.resize();
+    let capacity = vec.capacity();
+    assert_eq!(vec.generations(), 2);
+
+    for pos in (10..capacity).step_by(100) {
+        assert_eq!(vec.at(0, pos), None);
+        assert_eq!(vec.at_versioned(0, pos), None);
+    }
+
+    assert_eq!(vec.generations(), 2);
+    assert_eq!(vec.capacity(), capacity);
+    assert_eq!(vec.at(0, 9), Some(9));
+}
+
+#[test]
+fn threaded_at_has_no_phantom_or_missing_reads() {
+    let num_threads = 4;
+    let times = common::times(200);
+    let total = num_threads * times;
+
+    let vec = Arc::new(WaitFreeVector::new(1, num_threads + 1));
+    let pushers = common::spawn_pushers(&vec, num_threads, times);
+
+    // Remembers the first value read at every position. Nothing is popped,
+    // so a value once read has to stay and the spot before it has to be
+    // filled already.
+    let vec_thread = vec.clone();
+    let reader = thread::spawn(move || {
+        let mut seen = vec![None; total];
+
+        for round in 0..5 * total {
+            let pos = round % total;
+            let value = vec_thread.at(num_threads, pos);
+
+            if let Some(first) = seen[pos] {
+                assert_eq!(value, Some(first), "value at {} changed", pos);
+            }
+            if value.is_some() {
+                seen[pos] = value;
+                if pos > 0 {
+                    assert!(vec_thread.at(num_threads, pos - 1).is_some(), "hole below {}", pos);
+                }
+            }
+        }
+
+        seen
+    });
+
+    common::join(pushers);
+    let seen = reader.join().unwrap();
+
+    for (pos, first) in seen.into_iter().enumerate() {
+        if first.is_some() {
+            assert_eq!(vec.at(0, pos), first);
+        }
+    }
+}
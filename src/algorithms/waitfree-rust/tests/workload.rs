@@ -0,0 +1,37 @@
+use waitfree_rust::WaitFreeVector;
+use workload::MIXES;
+
+#[test]
+fn pushback_keeps_every_value() {
+    let num_threads = 4;
+    let per_thread = 30;
+    let v = workload::pushback::<WaitFreeVector>(num_threads, per_thread);
+
+    assert_eq!(v.length(), num_threads * per_thread);
+
+    let mut seen: Vec<usize> = (0..v.length()).map(|i| v.at(0, i).unwrap()).collect();
+    seen.sort_unstable();
+
+    let mut expected: Vec<usize> = (0..num_threads)
+        .flat_map(|i| (0..per_thread).map(move |j| (i + 1) * 100 + j))
+        .collect();
+    expected.sort_unstable();
+
+    assert_eq!(seen, expected);
+}
+
+#[test]
+fn popback_drains_everything_once() {
+    let num_threads = 4;
+    let (v, popped) = workload::popback::<WaitFreeVector>(num_threads, 30);
+
+    assert_eq!(popped, (0..num_threads).sum());
+    assert_eq!(v.length(), 0);
+}
+
+#[test]
+fn mixed_runs_to_completion() {
+    for &mix in MIXES.iter() {
+        workload::run_mixed::<WaitFreeVector>(4, mix, if cfg!(miri) { 50 } else { 2000 });
+    }
+}
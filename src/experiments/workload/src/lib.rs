@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use rand::Rng;
+
+// The operations every vector under test has to provide. Implementations
+// that do not need a thread id (e.g. LockVector) are free to ignore `tid`.
+pub trait ConcurrentVector: Send + Sync + 'static {
+    fn with_threads(capacity: usize, num_threads: usize) -> Self;
+    fn push_back(&self, tid: usize, value: usize);
+    fn pop_back(&self, tid: usize) -> Option<usize>;
+    fn at(&self, tid: usize, pos: usize) -> Option<usize>;
+    fn cwrite(&self, tid: usize, pos: usize, old: usize, new: usize) -> bool;
+    fn length(&self) -> usize;
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mix {
+    Insert,
+    Erase,
+}
+
+pub const MIXES: [Mix; 2] = [Mix::Insert, Mix::Erase];
+
+// Every thread pushes `per_thread` values of the form (tid + 1) * 100 + j.
+pub fn pushback<V: ConcurrentVector>(num_threads: usize, per_thread: usize) -> Arc<V> {
+    let v = Arc::new(V::with_threads(num_threads, num_threads));
+    let mut threads = Vec::new();
+
+    for i in 0..num_threads {
+        let thread_v = v.clone();
+        threads.push(thread::spawn(move || {
+            for j in 0..per_thread {
+                thread_v.push_back(i, (i + 1) * 100 + j);
+            }
+        }));
+    }
+
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    v
+}
+
+// Fills the vector with 0..num_threads and lets every thread pop `per_thread`
+// times. Returns the vector and the sum of everything that was popped.
+pub fn popback<V: ConcurrentVector>(num_threads: usize, per_thread: usize) -> (Arc<V>, usize) {
+    let v = Arc::new(V::with_threads(num_threads, num_threads));
+    let mut threads = Vec::new();
+
+    for i in 0..num_threads {
+        v.push_back(0, i);
+    }
+
+    for i in 0..num_threads {
+        let thread_v = v.clone();
+        threads.push(thread::spawn(move || {
+            let mut res = 0;
+            for _ in 0..per_thread {
+                if let Some(val) = thread_v.pop_back(i) {
+                    res += val;
+                }
+            }
+            res
+        }));
+    }
+
+    let popped = threads.into_iter().map(|t| t.join().unwrap()).sum();
+
+    (v, popped)
+}
+
+// One run of the mixed workload from the paper's evaluation: `max_ops` split
+// across `num_threads - 1` worker threads, 25% of them push_back and the rest
+// split between reads and (currently disabled) updates.
+pub fn run_mixed<V: ConcurrentVector>(num_threads: usize, mix: Mix, max_ops: usize) -> Duration {
+    let limit = 25;
+    let v = Arc::new(V::with_threads(num_threads + 1, num_threads));
+
+    let each_thread = max_ops / num_threads;
+    let extra = max_ops % num_threads;
+
+    let start_time = Instant::now();
+    for i in 0..10 {
+        v.push_back(0, i);
+    }
+
+    let mut threads = Vec::new();
+
+    for i in 1..num_threads {
+        let tot_ops = if i <= extra { each_thread + 1 } else { each_thread };
+        let thread_v = v.clone();
+
+        threads.push(thread::spawn(move || {
+            let mut rng = rand::thread_rng();
+            let mut r = || -> usize { rng.gen() };
+
+            for _ in 0..tot_ops {
+                let cur_op = r() % 3;
+                let do_pushback = r() % 100 < limit;
+
+                let x = r();
+                let size = thread_v.length();
+
+                if do_pushback {
+                    thread_v.push_back(i, x);
+                } else if size > 0 {
+                    match (mix, cur_op) {
+                        (Mix::Insert, 0) => {
+                            // thread_v.insertat(r() % size, x);
+                        },
+                        (Mix::Erase, 0) => {
+                            // thread_v.erase(r() % size);
+                            // thread_v.pop_back();
+                        },
+                        (_, 1) => {
+                            thread_v.at(i, r() % size);
+                        },
+                        _ => {
+                            let pos = r() % size;
+                            if let Some(_old) = thread_v.at(i, pos) {
+                                // thread_v.cwrite(i, pos, _old, x);
+                            }
+                        },
+                    }
+                }
+            }
+        }));
+    }
+
+    for t in threads {
+        t.join().unwrap();
+    }
+
+    start_time.elapsed()
+}
+
+// Prints one CSV row per thread count: threads, insert ms, erase ms.
+pub fn test_all<V: ConcurrentVector>(max_num_threads: usize, max_ops: usize) {
+    for num_threads in 1..max_num_threads + 1 {
+        print!("{}", num_threads);
+
+        for &mix in MIXES.iter() {
+            let elapsed_time = run_mixed::<V>(num_threads, mix, max_ops);
+            print!(",{:?}", elapsed_time.as_millis());
+        }
+        println!();
+    }
+}